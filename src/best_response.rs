@@ -0,0 +1,616 @@
+//! Best responses to a fixed strategy, and the exploitability they imply.
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use strum::IntoEnumIterator;
+
+use crate::game::*;
+use crate::match_format::*;
+use crate::strategy::Strategy;
+use crate::table::*;
+
+/// The best pure response of each player to a fixed strategy.
+#[derive(Serialize, Deserialize, Default)]
+pub struct BestResponse {
+    pub p1_value: InfoStateTable,
+    pub p2_value: InfoStateTable,
+    pub strategy: Strategy,
+}
+
+impl BestResponse {
+    /// Compute the best response to `strategy`, whose counterfactual
+    /// probabilities must be given in `counterfactual_probs`.
+    pub fn new(
+        strategy: &Strategy,
+        tree: &GameTree,
+        counterfactual_probs: &MetaStateTable,
+        outcome_values: &OutcomeValues,
+    ) -> BestResponse {
+        // Each player's values for each of their goals only depend on each
+        // other, so they can be computed independently.
+        let tasks: Vec<(Player, Outcome)> = [Player::Player1, Player::Player2]
+            .into_iter()
+            .flat_map(|player| Outcome::iter().map(move |goal| (player, goal)))
+            .collect();
+        let results: Vec<(Vec<f64>, Vec<Option<usize>>)> = tasks
+            .par_iter()
+            .map(|&(player, goal)| {
+                best_response_for_goal(
+                    strategy,
+                    tree,
+                    counterfactual_probs,
+                    outcome_values,
+                    player,
+                    goal,
+                )
+            })
+            .collect();
+
+        let mut p1_value = InfoStateTable::new(tree, 0.0);
+        let mut p2_value = InfoStateTable::new(tree, 0.0);
+        let mut result = Strategy {
+            probs: ActionTable::new(tree, 0.0),
+        };
+        for (&(player, goal), (values, best_indices)) in tasks.iter().zip(results) {
+            match player {
+                Player::Player1 => &mut p1_value,
+                Player::Player2 => &mut p2_value,
+            }
+            .goal_mut(goal)
+            .copy_from_slice(&values);
+            for (state, best_index) in best_indices.into_iter().enumerate() {
+                if let Some(ind) = best_index {
+                    result.probs[&InfoState { state, goal }][ind] = 1.0;
+                }
+            }
+        }
+        BestResponse {
+            p1_value,
+            p2_value,
+            strategy: result,
+        }
+    }
+}
+
+/// The values of `player` with `goal` at every state when best responding to
+/// `strategy`, and the best move at each state where `player` moves.
+fn best_response_for_goal(
+    strategy: &Strategy,
+    tree: &GameTree,
+    counterfactual_probs: &MetaStateTable,
+    outcome_values: &OutcomeValues,
+    player: Player,
+    goal: Outcome,
+) -> (Vec<f64>, Vec<Option<usize>>) {
+    let num_states = tree.states.len();
+    let mut unnormalized_value = vec![0.0; num_states];
+    let mut normalizing_sum = vec![0.0; num_states];
+    for i in (0..num_states).rev() {
+        let current_player = tree.current_player[i];
+        let active = current_player == player;
+        for other_goal in Outcome::iter() {
+            let metastate = match player {
+                Player::Player1 => MetaState {
+                    state: i,
+                    p1goal: goal,
+                    p2goal: other_goal,
+                },
+                Player::Player2 => MetaState {
+                    state: i,
+                    p1goal: other_goal,
+                    p2goal: goal,
+                },
+            };
+            let denominator = |c: StateId| {
+                let denom = normalizing_sum[c];
+                if denom == 0.0 {
+                    1.0
+                } else {
+                    denom
+                }
+            };
+            let value = if let Some(outcomes) = metastate.outcomes(tree) {
+                outcome_values.evaluate(&metastate, tree, outcomes)
+            } else if active {
+                tree.children[i]
+                    .iter()
+                    .map(|&c| unnormalized_value[c] / denominator(c))
+                    .reduce(if current_player == Player::Player1 {
+                        f64::max
+                    } else {
+                        f64::min
+                    })
+                    .unwrap()
+            } else {
+                itertools::zip(
+                    strategy.probs[&metastate.info_state(tree)].iter(),
+                    tree.children[i].iter(),
+                )
+                .map(|(p, &c)| p * unnormalized_value[c] / denominator(c))
+                .sum()
+            };
+            // The player to move is weighted by how likely the opponent is
+            // to get here, the other player by how likely the opponent is to
+            // get to the move before.
+            let weight = if active {
+                counterfactual_probs[&metastate]
+            } else {
+                metastate
+                    .parent(tree)
+                    .map(|p| counterfactual_probs[&p])
+                    .unwrap_or(tree.goals.prob(metastate.p1goal, metastate.p2goal))
+            };
+            unnormalized_value[i] += weight * value;
+            normalizing_sum[i] += weight;
+        }
+    }
+
+    let mut best_indices = vec![None; num_states];
+    for i in (0..num_states).rev() {
+        unnormalized_value[i] /= normalizing_sum[i];
+        if tree.current_player[i] != player {
+            continue;
+        }
+        let mut best_value = None;
+        let mut best_index = None;
+        for (ind, &c) in tree.children[i].iter().enumerate() {
+            let value = unnormalized_value[c];
+            if best_value.is_none()
+                || (player == Player::Player1 && value > best_value.unwrap())
+                || (player == Player::Player2 && value < best_value.unwrap())
+            {
+                best_value = Some(value);
+                best_index = Some(ind);
+            }
+        }
+        best_indices[i] = best_index;
+    }
+    (unnormalized_value, best_indices)
+}
+
+/// How much the best responses for both players together gain against
+/// `strategy`.  Zero exactly at a Nash equilibrium.
+pub fn exploitability_bound(
+    game_tree: &GameTree,
+    strategy: &Strategy,
+    outcome_values: &OutcomeValues,
+) -> f64 {
+    ExploitabilityReport::new(game_tree, strategy, outcome_values).nash_conv
+}
+
+/// The number of departures kept in an [`ExploitabilityReport`].
+pub const REPORTED_DEPARTURES: usize = 10;
+
+/// Where and by how much a strategy can be exploited.  Values are for the
+/// first player, and gains are how much the best responding player improves
+/// on the strategy's value, from their own point of view.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ExploitabilityReport {
+    /// The value of the strategy played against itself.
+    pub game_value: f64,
+    pub p1_gain: f64,
+    pub p2_gain: f64,
+    /// The sum of both players' gains, which is what
+    /// [`exploitability_bound`] returns.
+    pub nash_conv: f64,
+    pub goal_pairs: Vec<GoalPairGains>,
+    pub own_goals: Vec<OwnGoalGain>,
+    /// The decisions where best responding gains the most, most first.
+    pub departures: Vec<Departure>,
+}
+
+/// Both players' gains once a pair of goals has been dealt.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GoalPairGains {
+    pub p1goal: Outcome,
+    pub p2goal: Outcome,
+    pub p1_gain: f64,
+    pub p2_gain: f64,
+}
+
+/// A player's gain once they know their own goal but not the opponent's.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OwnGoalGain {
+    pub player: Player,
+    pub goal: Outcome,
+    pub gain: f64,
+}
+
+/// An information state where the best response plays differently from the
+/// strategy.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Departure {
+    pub player: Player,
+    pub goal: Outcome,
+    /// The moves so far, written like the states of [`crate::portable`].
+    pub moves: String,
+    /// The square the best response plays on, counting from 1.
+    pub square: usize,
+    /// How often the strategy plays that square.
+    pub strategy_prob: f64,
+    /// How much the best response's move is worth over the strategy's moves
+    /// here, when both go on best responding.
+    pub gain: f64,
+    /// How likely the opponent and the deal are to reach this information
+    /// state.  The departure is ranked by `reach * gain`.
+    pub reach: f64,
+}
+
+impl ExploitabilityReport {
+    pub fn new(
+        game_tree: &GameTree,
+        strategy: &Strategy,
+        outcome_values: &OutcomeValues,
+    ) -> ExploitabilityReport {
+        let counterfactual_probs = strategy.counterfactual_probs(game_tree);
+        let best_response =
+            BestResponse::new(strategy, game_tree, &counterfactual_probs, outcome_values);
+        ExploitabilityReport::with_best_response(
+            game_tree,
+            strategy,
+            &best_response,
+            &counterfactual_probs,
+            outcome_values,
+        )
+    }
+
+    /// The report for a `best_response` to `strategy` that has already been
+    /// computed.
+    pub fn with_best_response(
+        game_tree: &GameTree,
+        strategy: &Strategy,
+        best_response: &BestResponse,
+        counterfactual_probs: &MetaStateTable,
+        outcome_values: &OutcomeValues,
+    ) -> ExploitabilityReport {
+        let values = strategy.expected_values(game_tree, outcome_values);
+        let p1_exploiter = Strategy::splice(strategy, &best_response.strategy, game_tree);
+        let p2_exploiter = Strategy::splice(&best_response.strategy, strategy, game_tree);
+        let p1_exploiter_values = p1_exploiter.expected_values(game_tree, outcome_values);
+        let p2_exploiter_values = p2_exploiter.expected_values(game_tree, outcome_values);
+
+        let root = |table: &MetaStateTable, p1goal, p2goal| {
+            table[&MetaState {
+                state: 0,
+                p1goal,
+                p2goal,
+            }]
+        };
+        let mut goal_pairs = Vec::new();
+        for p1goal in Outcome::iter() {
+            for p2goal in Outcome::iter() {
+                let value = root(&values, p1goal, p2goal);
+                goal_pairs.push(GoalPairGains {
+                    p1goal,
+                    p2goal,
+                    p1_gain: root(&p2_exploiter_values, p1goal, p2goal) - value,
+                    p2_gain: value - root(&p1_exploiter_values, p1goal, p2goal),
+                });
+            }
+        }
+
+        // Goals that are never dealt have no gain to report.
+        let mut own_goals = Vec::new();
+        for player in [Player::Player1, Player::Player2] {
+            for goal in Outcome::iter() {
+                let mut total_prob = 0.0;
+                let mut total_gain = 0.0;
+                for pair in &goal_pairs {
+                    let (own_goal, gain) = match player {
+                        Player::Player1 => (pair.p1goal, pair.p1_gain),
+                        Player::Player2 => (pair.p2goal, pair.p2_gain),
+                    };
+                    let prob = game_tree.goals.prob(pair.p1goal, pair.p2goal);
+                    if own_goal == goal && prob > 0.0 {
+                        total_prob += prob;
+                        total_gain += prob * gain;
+                    }
+                }
+                if total_prob > 0.0 {
+                    own_goals.push(OwnGoalGain {
+                        player,
+                        goal,
+                        gain: total_gain / total_prob,
+                    });
+                }
+            }
+        }
+
+        let game_value = values.root_value(game_tree);
+        let p1_value = p2_exploiter_values.root_value(game_tree);
+        let p2_value = p1_exploiter_values.root_value(game_tree);
+        ExploitabilityReport {
+            game_value,
+            p1_gain: p1_value - game_value,
+            p2_gain: game_value - p2_value,
+            nash_conv: p1_value - p2_value,
+            goal_pairs,
+            own_goals,
+            departures: departures(game_tree, strategy, best_response, counterfactual_probs),
+        }
+    }
+}
+
+/// The [`REPORTED_DEPARTURES`] information states where `best_response`
+/// gains the most by not playing `strategy`.
+fn departures(
+    tree: &GameTree,
+    strategy: &Strategy,
+    best_response: &BestResponse,
+    counterfactual_probs: &MetaStateTable,
+) -> Vec<Departure> {
+    let mut result = Vec::new();
+    for state in 0..tree.states.len() {
+        if tree.children[state].is_empty() {
+            continue;
+        }
+        let player = tree.current_player[state];
+        let (value, sign) = match player {
+            Player::Player1 => (&best_response.p1_value, 1.0),
+            Player::Player2 => (&best_response.p2_value, -1.0),
+        };
+        for goal in Outcome::iter() {
+            let infostate = InfoState { state, goal };
+            let reach: f64 = Outcome::iter()
+                .map(|other_goal| {
+                    let (p1goal, p2goal) = match player {
+                        Player::Player1 => (goal, other_goal),
+                        Player::Player2 => (other_goal, goal),
+                    };
+                    counterfactual_probs[&MetaState {
+                        state,
+                        p1goal,
+                        p2goal,
+                    }]
+                })
+                .sum();
+            if reach <= 0.0 {
+                continue;
+            }
+            let child_value = |c: StateId| value[&InfoState { state: c, goal }];
+            let probs = &strategy.probs[&infostate];
+            let best_index = best_response.strategy.probs[&infostate]
+                .iter()
+                .position(|&p| p == 1.0)
+                .unwrap();
+            let strategy_value: f64 = itertools::zip(probs.iter(), tree.children[state].iter())
+                .map(|(p, &c)| p * child_value(c))
+                .sum();
+            let best_child = tree.children[state][best_index];
+            let gain = sign * (child_value(best_child) - strategy_value);
+            if gain <= 0.0 {
+                continue;
+            }
+            result.push(Departure {
+                player,
+                goal,
+                moves: crate::portable::state_key(&tree.states[state]),
+                square: tree.states[best_child].last_move().unwrap() + 1,
+                strategy_prob: probs[best_index],
+                gain,
+                reach,
+            });
+        }
+    }
+    result.sort_by(|a, b| (b.reach * b.gain).total_cmp(&(a.reach * a.gain)));
+    result.truncate(REPORTED_DEPARTURES);
+    result
+}
+
+fn player_name(player: Player) -> &'static str {
+    match player {
+        Player::Player1 => "First",
+        Player::Player2 => "Second",
+    }
+}
+
+impl std::fmt::Display for ExploitabilityReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Value of the strategy {}", self.game_value)?;
+        writeln!(
+            f,
+            "First player gains {} and second player gains {} by best responding, NashConv {}",
+            self.p1_gain, self.p2_gain, self.nash_conv
+        )?;
+        for pair in &self.goal_pairs {
+            writeln!(
+                f,
+                "  With goals {} {}: first player gains {}, second player gains {}",
+                pair.p1goal, pair.p2goal, pair.p1_gain, pair.p2_gain
+            )?;
+        }
+        for own_goal in &self.own_goals {
+            writeln!(
+                f,
+                "  {} player with goal {} gains {}",
+                player_name(own_goal.player),
+                own_goal.goal,
+                own_goal.gain
+            )?;
+        }
+        write!(
+            f,
+            "Largest departures of the best response from the strategy:"
+        )?;
+        if self.departures.is_empty() {
+            write!(f, " none")?;
+        }
+        for departure in &self.departures {
+            write!(
+                f,
+                "\n  {} player with goal {} after \"{}\": plays {} (strategy plays it {:.1}%), gains {} where reached with probability {}",
+                player_name(departure.player),
+                departure.goal,
+                departure.moves,
+                departure.square,
+                100.0 * departure.strategy_prob,
+                departure.gain,
+                departure.reach
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// How much a best responder can get against a bot playing a whole match,
+/// in one subgame.  Values are for the subgame's first player, who in
+/// `max_value` best responds to the bot in every round of the rest of the
+/// match, while in `min_value` the second player does.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct SubgameBounds {
+    pub max_value: f64,
+    pub min_value: f64,
+}
+
+impl SubgameBounds {
+    /// How much both best responders together gain against the bot.  Zero
+    /// exactly when the bot plays a Nash equilibrium of the match.
+    pub fn exploitability(&self) -> f64 {
+        self.max_value - self.min_value
+    }
+}
+
+/// The bounds of every subgame of a match in `format` against a bot playing
+/// `strategies` in each subgame.
+///
+/// The groups of [`MatchFormat::solve_order`] lead back to themselves, so
+/// the bounds of each group are found together: starting from the worst
+/// possible values, each step best responds to the bot against the values
+/// found so far and solves for the group's values under those responses,
+/// until they stop improving.  Groups are done from the last rounds to the
+/// first, so the subgames they lead to outside the group are already known.
+pub fn match_exploitability(
+    tree: &GameTree,
+    format: &MatchFormat,
+    strategies: &HashMap<Subgame, &Strategy>,
+) -> HashMap<Subgame, SubgameBounds> {
+    let mut max_ev = HashMap::<Subgame, f64>::new();
+    let mut min_ev = HashMap::<Subgame, f64>::new();
+    for group in format.solve_order().into_iter().flatten() {
+        for subgame in &group {
+            // Initialize with bad results so we will always improve.
+            max_ev.insert(subgame.clone(), -1.0);
+            min_ev.insert(subgame.clone(), 1.0);
+        }
+        let index = |subgame: &Subgame| group.iter().position(|s| s == subgame);
+        loop {
+            // How often each outcome of each subgame happens when its first
+            // (second) player best responds.  After a round where the players
+            // swap, the best responder is the other player, so the values of
+            // the next round come from the other bound.
+            let best_outcomes: Vec<[HashMap<(bool, bool), f64>; 2]> = group
+                .par_iter()
+                .map(|subgame| {
+                    let strategy = strategies[subgame];
+                    let max_outcome_values = format.outcome_values_with(subgame, |s, swapped| {
+                        if swapped {
+                            -min_ev[s]
+                        } else {
+                            max_ev[s]
+                        }
+                    });
+                    let min_outcome_values = format.outcome_values_with(subgame, |s, swapped| {
+                        if swapped {
+                            -max_ev[s]
+                        } else {
+                            min_ev[s]
+                        }
+                    });
+                    let counterfactual_probs = strategy.counterfactual_probs(tree);
+                    let p1_best_response = BestResponse::new(
+                        strategy,
+                        tree,
+                        &counterfactual_probs,
+                        &max_outcome_values,
+                    );
+                    let p2_best_response = BestResponse::new(
+                        strategy,
+                        tree,
+                        &counterfactual_probs,
+                        &min_outcome_values,
+                    );
+                    let p1_exploiter = Strategy::splice(strategy, &p2_best_response.strategy, tree);
+                    let p2_exploiter = Strategy::splice(&p1_best_response.strategy, strategy, tree);
+                    [
+                        p2_exploiter.outcome_probs(tree),
+                        p1_exploiter.outcome_probs(tree),
+                    ]
+                })
+                .collect();
+
+            // With the responses fixed, the values of the group are a linear
+            // system: unknown 2 * i is the max value of subgame i and
+            // 2 * i + 1 its min value.
+            let n = 2 * group.len();
+            let mut matrix = vec![vec![0.0; n]; n];
+            let mut rhs = vec![0.0; n];
+            for (i, subgame) in group.iter().enumerate() {
+                for (bound, outcome_probs) in best_outcomes[i].iter().enumerate() {
+                    let row = 2 * i + bound;
+                    matrix[row][row] += 1.0;
+                    for outcomes in ROUND_OUTCOMES {
+                        let prob = outcome_probs[&outcomes];
+                        match format.next(subgame, outcomes) {
+                            Next::Over(value) => rhs[row] += prob * value,
+                            Next::Round { subgame, swapped } => {
+                                let (sign, next_bound) = if swapped {
+                                    (-1.0, bound ^ 1)
+                                } else {
+                                    (1.0, bound)
+                                };
+                                match index(&subgame) {
+                                    Some(j) => matrix[row][2 * j + next_bound] -= sign * prob,
+                                    None => {
+                                        let evs = if next_bound == 0 { &max_ev } else { &min_ev };
+                                        rhs[row] += sign * prob * evs[&subgame];
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            let values = solve_linear(matrix, rhs);
+
+            let mut changed = false;
+            for (i, subgame) in group.iter().enumerate() {
+                for (bound, evs) in [(0, &mut max_ev), (1, &mut min_ev)] {
+                    let value = values[2 * i + bound];
+                    log::debug!(
+                        "After iteration of best response, {} value of subgame {} is {}",
+                        if bound == 0 { "max" } else { "min" },
+                        subgame,
+                        value
+                    );
+                    let prev_ev = evs.get_mut(subgame).unwrap();
+                    let improved = if bound == 0 {
+                        value > *prev_ev
+                    } else {
+                        value < *prev_ev
+                    };
+                    if improved {
+                        *prev_ev = value;
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+    }
+    max_ev
+        .into_iter()
+        .map(|(subgame, max_value)| {
+            let min_value = min_ev[&subgame];
+            (
+                subgame,
+                SubgameBounds {
+                    max_value,
+                    min_value,
+                },
+            )
+        })
+        .collect()
+}
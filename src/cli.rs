@@ -0,0 +1,258 @@
+//! Command line arguments shared by the `tictac` subcommands, with loaders
+//! for what they name.  Each group of arguments is a [`clap::Args`] struct
+//! to `#[clap(flatten)]` into a command.
+
+use clap::ArgAction;
+use clap::Args;
+use clap::ValueEnum;
+use clap::ValueHint;
+use std::path::{Path, PathBuf};
+
+use crate::convergence::ConvergenceLog;
+use crate::game::*;
+use crate::io;
+use crate::match_format::MatchFormat;
+use crate::play::MatchStrategies;
+use crate::solver::{CFRDiscounting, CFRVariant};
+
+/// How much a binary prints.  Progress is printed at the info level, which
+/// is the default; the library itself prints nothing unless a binary sets
+/// up a logger at this level.
+#[derive(Args, Debug, Clone)]
+pub struct VerbosityArgs {
+    /// Print more.  -v adds the values of every iteration, -vv everything
+    /// the solvers compute along the way.
+    #[clap(short, long, action = ArgAction::Count, global = true)]
+    pub verbose: u8,
+
+    /// Print less.  -q only prints warnings and results, -qq only errors and
+    /// results.
+    #[clap(short, long, action = ArgAction::Count, global = true, conflicts_with = "verbose")]
+    pub quiet: u8,
+}
+
+impl VerbosityArgs {
+    pub fn level(&self) -> log::LevelFilter {
+        match self.verbose as i32 - self.quiet as i32 {
+            i32::MIN..=-2 => log::LevelFilter::Error,
+            -1 => log::LevelFilter::Warn,
+            0 => log::LevelFilter::Info,
+            1 => log::LevelFilter::Debug,
+            2..=i32::MAX => log::LevelFilter::Trace,
+        }
+    }
+}
+
+/// The game being played.
+#[derive(Args, Debug, Clone)]
+pub struct TreeArgs {
+    /// The board to play on, as ROWSxCOLS or ROWSxCOLS:K for K in a row.
+    #[clap(long, default_value = "3x3")]
+    pub board: Board,
+
+    /// How goals are dealt: uniform, distinct (never equal), marginal:W,L,T
+    /// or joint: followed by 9 weights for WW,WL,WT,LW,...,TT.
+    #[clap(long, default_value = "uniform")]
+    pub goals: GoalDistribution,
+}
+
+impl TreeArgs {
+    /// Build the game tree, saying how big it is.
+    pub fn build(&self) -> GameTree {
+        log::info!("Constructing game tree...");
+        let game_tree = GameTree::with_board(self.board).with_goals(self.goals);
+        log::info!("{} States in the game tree", game_tree.states.len());
+        log::info!("{} Terminal states", game_tree.num_terminals());
+        game_tree
+    }
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ThreadArgs {
+    /// The number of threads to use.  0 uses one per CPU.
+    #[clap(long, default_value_t = 0)]
+    pub threads: usize,
+}
+
+impl ThreadArgs {
+    /// Set up the global thread pool.
+    pub fn init(&self) {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(self.threads)
+            .build_global()
+            .unwrap();
+    }
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+pub enum Solver {
+    /// Vanilla CFR.
+    Cfr,
+    /// Discounted CFR.
+    Dcfr,
+    /// CFR+.
+    CfrPlus,
+}
+
+/// How CFR is run.
+#[derive(Args, Debug, Clone)]
+pub struct SolverArgs {
+    /// Which CFR variant to run.  The discount parameters only apply to dcfr.
+    #[clap(long, value_enum, default_value_t = Solver::Dcfr)]
+    pub solver: Solver,
+
+    #[clap(long, default_value_t = 1.5)]
+    pub discount_alpha: f64,
+    #[clap(long, default_value_t = 0.0)]
+    pub discount_beta: f64,
+    #[clap(long, default_value_t = 2.0)]
+    pub discount_gamma: f64,
+
+    #[clap(long, action = ArgAction::Set,  default_value_t = true)]
+    pub alternate_updates: bool,
+
+    /// Epsilon reward for playing "small" moves to encourage regularization.
+    #[clap(long, default_value_t = 0.0)]
+    pub small_move_epsilon: f64,
+    /// How much the epsilon shrinks by each iteration.
+    #[clap(long, default_value_t = 0.0)]
+    pub small_move_epsilon_decay: f64,
+}
+
+impl SolverArgs {
+    pub fn variant(&self) -> CFRVariant {
+        match self.solver {
+            Solver::Cfr => CFRVariant::Vanilla,
+            Solver::Dcfr => CFRVariant::Discounted(CFRDiscounting {
+                alpha: self.discount_alpha,
+                beta: self.discount_beta,
+                gamma: self.discount_gamma,
+            }),
+            Solver::CfrPlus => CFRVariant::Plus,
+        }
+    }
+
+    /// A manifest for a solve of `tree` by the `solver` command with these
+    /// settings.
+    pub fn manifest(&self, solver: &str, tree: &GameTree) -> io::Manifest {
+        io::Manifest {
+            variant: Some(self.variant()),
+            alternate_updates: Some(self.alternate_updates),
+            small_move_epsilon: Some(self.small_move_epsilon),
+            small_move_epsilon_decay: Some(self.small_move_epsilon_decay),
+            ..io::Manifest::new(solver, tree)
+        }
+    }
+}
+
+/// Where and how often a solver logs its convergence.
+#[derive(Args, Debug, Clone)]
+pub struct LogArgs {
+    /// Write a line for every logged iteration to this file, as CSV or as
+    /// JSON Lines (one JSON object per line) depending on whether it ends in
+    /// .csv or .json.
+    #[clap(long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    pub log: Option<PathBuf>,
+
+    /// Log every this many iterations.  Each line measures the
+    /// exploitability of two strategies, so logging often is slow.
+    #[clap(long, default_value_t = 10)]
+    pub log_every: usize,
+}
+
+impl LogArgs {
+    /// Open the log, if there is one, or with `append` continue an older
+    /// one.
+    pub fn open(&self, append: bool) -> Result<Option<ConvergenceLog>, String> {
+        self.log
+            .as_ref()
+            .map(|path| ConvergenceLog::create(path, append))
+            .transpose()
+    }
+
+    /// Whether iteration `i` should be logged.
+    pub fn logs(&self, i: usize) -> bool {
+        self.log_every > 0 && i % self.log_every == self.log_every - 1
+    }
+}
+
+/// A saved solution of a single round.
+#[derive(Args, Debug, Clone)]
+pub struct SolutionArgs {
+    /// The solution directory, the output of `tictac solve`.
+    #[clap(short, long, parse(from_os_str), value_hint = ValueHint::DirPath)]
+    pub solutions_dir: PathBuf,
+
+    /// The iteration to load.  Defaults to the last one saved.
+    #[clap(short, long)]
+    pub iteration: Option<usize>,
+
+    #[clap(short, long, action = ArgAction::Set,  default_value_t = true)]
+    pub average_strategy: bool,
+}
+
+impl SolutionArgs {
+    /// Load the solution in `dir`, saying which strategy will be played.
+    pub fn load_from(&self, dir: &Path, tree: &GameTree) -> bincode::Result<io::Solution> {
+        let solution = match self.iteration {
+            Some(iteration) => io::load_solution(dir, iteration, tree),
+            None => io::load_latest_solution(dir, tree),
+        }?;
+        log::info!("{}", solution.describe(self.average_strategy));
+        Ok(solution)
+    }
+
+    pub fn load(&self, tree: &GameTree) -> bincode::Result<io::Solution> {
+        self.load_from(&self.solutions_dir, tree)
+    }
+}
+
+/// The rules of a match.
+#[derive(Args, Debug, Clone)]
+pub struct FormatArgs {
+    /// The score which wins the match, short for `--format first-to:N`.
+    #[clap(short, long, default_value_t = 5)]
+    pub winning_score: i8,
+
+    /// The rules of the match: first-to:N, win-by-two:N (first to N with a
+    /// lead of two) or rounds:N (N rounds, equal scores draw), optionally
+    /// followed by ,loser-first for the player who didn't score to move
+    /// first in the next round.
+    #[clap(long, conflicts_with = "winning-score")]
+    pub format: Option<MatchFormat>,
+}
+
+impl FormatArgs {
+    pub fn match_format(&self) -> MatchFormat {
+        self.format
+            .unwrap_or_else(|| MatchFormat::first_to(self.winning_score))
+    }
+}
+
+/// The match a solution is played in.
+#[derive(Args, Debug, Clone)]
+pub struct MatchArgs {
+    #[clap(flatten)]
+    pub format: FormatArgs,
+
+    #[clap(short, long, action = ArgAction::Set,  default_value_t = true)]
+    pub average_strategy: bool,
+}
+
+impl MatchArgs {
+    /// Load a bot for every subgame of the match from the output directory
+    /// of `tictac solve-match`, or play uniformly at random if `path` is
+    /// "uniform".
+    pub fn load_bot(&self, tree: &GameTree, path: &Path) -> bincode::Result<MatchStrategies> {
+        if path == Path::new("uniform") {
+            return Ok(MatchStrategies::uniform(tree, self.format.match_format()));
+        }
+        log::info!("Loading {}...", path.display());
+        MatchStrategies::load(
+            tree,
+            path,
+            self.format.match_format(),
+            self.average_strategy,
+        )
+    }
+}
@@ -0,0 +1,267 @@
+//! Per-iteration logs of how a solve converges, for plotting.
+//!
+//! A log is CSV or JSON, chosen by the extension of its path.  The CSV form
+//! has one row per logged iteration with the columns
+//! `iteration,p1score,p2score,round,ev,` then `ev_{p1goal}_{p2goal}` for each
+//! pair of goals, then
+//! `max_strategy_change,exploitability,average_exploitability,wall_time`,
+//! where the scores are left empty outside of a match.  The JSON form is
+//! JSON Lines, one [`ConvergenceRow`] object per line, so it can be read
+//! while the solve is still running.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Instant;
+use strum::IntoEnumIterator;
+
+use crate::best_response::exploitability_bound;
+use crate::game::*;
+use crate::portable::Format;
+use crate::strategy::Strategy;
+
+/// The value of the average strategy for one pair of goals.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GoalPairValue {
+    pub p1goal: Outcome,
+    pub p2goal: Outcome,
+    pub ev: f64,
+}
+
+/// The progress of a solve after one iteration.  Values are for the first
+/// player and, like the exploitabilities, against the outcome values the
+/// iteration was solved with.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConvergenceRow {
+    pub iteration: usize,
+    /// The subgame being solved, in a match.
+    pub subgame: Option<Subgame>,
+    /// The value of the average strategy.
+    pub ev: f64,
+    pub goal_evs: Vec<GoalPairValue>,
+    /// The largest change in any action probability made by the iteration.
+    pub max_strategy_change: f64,
+    /// The exploitability bound of the strategy the iteration played.
+    pub exploitability: f64,
+    /// The exploitability bound of the average strategy.
+    pub average_exploitability: f64,
+    /// Seconds since the log was started, counting the runs it continues.
+    pub wall_time: f64,
+}
+
+impl ConvergenceRow {
+    fn csv_header() -> String {
+        let mut columns = vec!["iteration", "p1score", "p2score", "round", "ev"]
+            .into_iter()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        for p1goal in Outcome::iter() {
+            for p2goal in Outcome::iter() {
+                columns.push(format!("ev_{}_{}", p1goal, p2goal).to_lowercase());
+            }
+        }
+        for column in [
+            "max_strategy_change",
+            "exploitability",
+            "average_exploitability",
+            "wall_time",
+        ] {
+            columns.push(column.to_string());
+        }
+        columns.join(",")
+    }
+
+    fn csv_line(&self) -> String {
+        let mut fields = vec![self.iteration.to_string()];
+        match &self.subgame {
+            Some(subgame) => {
+                fields.push(subgame.p1score.to_string());
+                fields.push(subgame.p2score.to_string());
+                fields.push(subgame.round.to_string());
+            }
+            None => fields.extend(["".to_string(), "".to_string(), "".to_string()]),
+        }
+        fields.push(self.ev.to_string());
+        fields.extend(self.goal_evs.iter().map(|goal_ev| goal_ev.ev.to_string()));
+        for value in [
+            self.max_strategy_change,
+            self.exploitability,
+            self.average_exploitability,
+            self.wall_time,
+        ] {
+            fields.push(value.to_string());
+        }
+        fields.join(",")
+    }
+}
+
+/// A convergence log being written.  Rows may be written from several
+/// threads, and each is flushed as soon as it is written.
+pub struct ConvergenceLog {
+    format: Format,
+    file: Mutex<BufWriter<File>>,
+    start: Instant,
+    /// The wall time of the last row of the log being continued.
+    earlier_wall_time: f64,
+}
+
+impl ConvergenceLog {
+    /// Start a log at `path`, replacing any older one, or with `append` add
+    /// to the end of it, counting wall time on from its last row.
+    pub fn create(path: &Path, append: bool) -> Result<ConvergenceLog, String> {
+        let format = match Format::of_path(path) {
+            Some(Format::Csv) => Format::Csv,
+            Some(Format::Json) => Format::Json,
+            _ => return Err(format!("{} should end in .csv or .json", path.display())),
+        };
+        let earlier_wall_time = if append && path.exists() {
+            last_wall_time(path, format)?
+        } else {
+            0.0
+        };
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(path)
+            .map_err(|e| format!("couldn't open {}: {}", path.display(), e))?;
+        let is_empty = file.metadata().map_err(|e| e.to_string())?.len() == 0;
+        let log = ConvergenceLog {
+            format,
+            file: Mutex::new(BufWriter::new(file)),
+            start: Instant::now(),
+            earlier_wall_time,
+        };
+        if format == Format::Csv && is_empty {
+            log.write_line(&ConvergenceRow::csv_header())?;
+        }
+        Ok(log)
+    }
+
+    /// Measure an iteration which played `strategy`, changed it by
+    /// `max_strategy_change`, and left `average_strategy` as the average,
+    /// and write it to the log.
+    #[allow(clippy::too_many_arguments)]
+    pub fn record(
+        &self,
+        tree: &GameTree,
+        iteration: usize,
+        subgame: Option<Subgame>,
+        strategy: &Strategy,
+        average_strategy: &Strategy,
+        max_strategy_change: f64,
+        outcome_values: &OutcomeValues,
+    ) -> Result<(), String> {
+        let expected_values = average_strategy.expected_values(tree, outcome_values);
+        let mut goal_evs = Vec::new();
+        for p1goal in Outcome::iter() {
+            for p2goal in Outcome::iter() {
+                goal_evs.push(GoalPairValue {
+                    p1goal,
+                    p2goal,
+                    ev: expected_values[&MetaState {
+                        state: 0,
+                        p1goal,
+                        p2goal,
+                    }],
+                });
+            }
+        }
+        let row = ConvergenceRow {
+            iteration,
+            subgame,
+            ev: expected_values.root_value(tree),
+            goal_evs,
+            max_strategy_change,
+            exploitability: exploitability_bound(tree, strategy, outcome_values),
+            average_exploitability: exploitability_bound(tree, average_strategy, outcome_values),
+            wall_time: self.earlier_wall_time + self.start.elapsed().as_secs_f64(),
+        };
+        self.write(&row)
+    }
+
+    pub fn write(&self, row: &ConvergenceRow) -> Result<(), String> {
+        let line = match self.format {
+            Format::Csv => row.csv_line(),
+            _ => serde_json::to_string(row).map_err(|e| e.to_string())?,
+        };
+        self.write_line(&line)
+    }
+
+    fn write_line(&self, line: &str) -> Result<(), String> {
+        let mut file = self.file.lock().unwrap();
+        writeln!(file, "{}", line).map_err(|e| e.to_string())?;
+        file.flush().map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
+
+/// The wall time of the last row of the log at `path`, or 0 if it has none.
+fn last_wall_time(path: &Path, format: Format) -> Result<f64, String> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("couldn't read {}: {}", path.display(), e))?;
+    let header_lines = if format == Format::Csv { 1 } else { 0 };
+    let rows = contents.lines().skip(header_lines);
+    let last = match rows.filter(|line| !line.trim().is_empty()).last() {
+        Some(last) => last,
+        None => return Ok(0.0),
+    };
+    let wall_time = match format {
+        Format::Csv => last.rsplit(',').next().unwrap().parse().ok(),
+        _ => serde_json::from_str::<ConvergenceRow>(last)
+            .ok()
+            .map(|row| row.wall_time),
+    };
+    wall_time.ok_or_else(|| format!("couldn't read the last row of {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(iteration: usize, wall_time: f64) -> ConvergenceRow {
+        ConvergenceRow {
+            iteration,
+            subgame: None,
+            ev: 0.0,
+            goal_evs: Vec::new(),
+            max_strategy_change: 0.0,
+            exploitability: 0.0,
+            average_exploitability: 0.0,
+            wall_time,
+        }
+    }
+
+    #[test]
+    fn appending_counts_on_from_the_last_row() {
+        let dir = std::env::temp_dir().join(format!("tictac-convergence-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["log.csv", "log.json"] {
+            let path = dir.join(name);
+            let log = ConvergenceLog::create(&path, false).unwrap();
+            assert_eq!(log.earlier_wall_time, 0.0);
+            log.write(&row(9, 1.5)).unwrap();
+            log.write(&row(19, 12.25)).unwrap();
+            drop(log);
+            assert_eq!(
+                ConvergenceLog::create(&path, true)
+                    .unwrap()
+                    .earlier_wall_time,
+                12.25
+            );
+            // Starting over forgets the old rows.
+            drop(ConvergenceLog::create(&path, false).unwrap());
+            assert_eq!(
+                ConvergenceLog::create(&path, true)
+                    .unwrap()
+                    .earlier_wall_time,
+                0.0
+            );
+            std::fs::remove_file(path).unwrap();
+        }
+        assert!(ConvergenceLog::create(&dir.join("log.txt"), false).is_err());
+    }
+}
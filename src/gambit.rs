@@ -0,0 +1,162 @@
+//! Export to [Gambit](http://www.gambit-project.org/)'s `.efg` extensive
+//! form format, so solutions can be checked with independent solvers.
+//!
+//! The exported game is the same one the solvers work on: a chance move
+//! dealing the goals from the tree's goal distribution, followed by the
+//! symmetry-reduced game tree, with one information set per [`InfoState`].
+//! Moves are labelled with the square they are played on, numbered from 1 in
+//! row-major order.
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+use strum::IntoEnumIterator;
+
+use crate::game::*;
+use crate::table::goal_pair_index;
+
+/// Write the round described by `tree` and `outcome_values` to `out`.  The
+/// second player's payoffs are the negation of the first player's.
+pub fn write_efg<W: Write>(
+    out: &mut W,
+    tree: &GameTree,
+    outcome_values: &OutcomeValues,
+    title: &str,
+) -> io::Result<()> {
+    writeln!(
+        out,
+        "EFG 2 R \"{}\" {{ \"Player 1\" \"Player 2\" }}",
+        escape(title)
+    )?;
+    writeln!(
+        out,
+        "\"Imperfect information tic tac toe on a {} board, goals dealt {}\"",
+        tree.board, tree.goals
+    )?;
+    writeln!(out)?;
+
+    write!(out, "c \"\" 1 \"goals\" {{")?;
+    for p1goal in Outcome::iter() {
+        for p2goal in Outcome::iter() {
+            write!(
+                out,
+                " \"{} {}\" {}",
+                p1goal,
+                p2goal,
+                goal_prob(&tree.goals, p1goal, p2goal)
+            )?;
+        }
+    }
+    writeln!(out, " }} 0")?;
+
+    let mut writer = EfgWriter {
+        tree,
+        outcome_values,
+        infosets: [HashMap::new(), HashMap::new()],
+        outcomes: HashMap::new(),
+    };
+    for p1goal in Outcome::iter() {
+        for p2goal in Outcome::iter() {
+            writer.write_node(
+                out,
+                &MetaState {
+                    state: 0,
+                    p1goal,
+                    p2goal,
+                },
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// The probability of a goal pair, as an exact fraction when the weights are
+/// whole numbers.
+fn goal_prob(goals: &GoalDistribution, p1goal: Outcome, p2goal: Outcome) -> String {
+    let weights = goals.weights();
+    if weights.iter().all(|w| w.fract() == 0.0) {
+        let total: f64 = weights.iter().sum();
+        return format!("{}/{}", weights[goal_pair_index(p1goal, p2goal)], total);
+    }
+    goals.prob(p1goal, p2goal).to_string()
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+struct EfgWriter<'a> {
+    tree: &'a GameTree,
+    outcome_values: &'a OutcomeValues,
+    /// Gambit's information set numbers for each player, which count from 1
+    /// in the order the sets are first written.
+    infosets: [HashMap<InfoState, usize>; 2],
+    /// Gambit's outcome numbers, keyed by the first player's payoff.
+    outcomes: HashMap<u64, usize>,
+}
+
+impl EfgWriter<'_> {
+    /// Write `metastate` and everything below it, in the depth-first order
+    /// Gambit expects.
+    fn write_node<W: Write>(&mut self, out: &mut W, metastate: &MetaState) -> io::Result<()> {
+        let tree = self.tree;
+        if let Some(outcomes) = metastate.outcomes(tree) {
+            // Adding 0 turns -0 into 0.
+            let value = self.outcome_values.evaluate(metastate, tree, outcomes) + 0.0;
+            let next_outcome = self.outcomes.len() + 1;
+            let outcome = *self.outcomes.entry(value.to_bits()).or_insert(next_outcome);
+            if outcome == next_outcome {
+                let name = match outcomes {
+                    (true, true) => "both score",
+                    (true, false) => "first player scores",
+                    (false, true) => "second player scores",
+                    (false, false) => "neither scores",
+                };
+                return writeln!(
+                    out,
+                    "t \"\" {} \"{}\" {{ {}, {} }}",
+                    outcome,
+                    name,
+                    value,
+                    -value + 0.0
+                );
+            }
+            return writeln!(out, "t \"\" {}", outcome);
+        }
+
+        let player = tree.current_player[metastate.state];
+        let infostate = metastate.info_state(tree);
+        let infosets = &mut self.infosets[player as usize];
+        let next_infoset = infosets.len() + 1;
+        let infoset = *infosets.entry(infostate).or_insert(next_infoset);
+        if infoset == next_infoset {
+            write!(
+                out,
+                "p \"\" {} {} \"{}\" {{",
+                player as usize + 1,
+                infoset,
+                infoset_name(tree, &infostate)
+            )?;
+            for &child in tree.children[metastate.state].iter() {
+                write!(out, " \"{}\"", tree.states[child].last_move().unwrap() + 1)?;
+            }
+            writeln!(out, " }} 0")?;
+        } else {
+            writeln!(out, "p \"\" {} {} 0", player as usize + 1, infoset)?;
+        }
+        for child in metastate.children(tree) {
+            self.write_node(out, &child)?;
+        }
+        Ok(())
+    }
+}
+
+/// The goal of the player to move and the squares played so far, in order.
+fn infoset_name(tree: &GameTree, infostate: &InfoState) -> String {
+    let cells = tree.states[infostate.state].cells();
+    let mut name = infostate.goal.to_string();
+    for move_num in 1..=cells.iter().copied().max().unwrap_or(0) {
+        let square = cells.iter().position(|&m| m == move_num).unwrap();
+        name += &format!(" {}", square + 1);
+    }
+    name
+}
@@ -0,0 +1,990 @@
+//! The game itself: boards, the game tree, goals and payoffs.
+
+use rand::{
+    distributions::{Distribution, Standard, WeightedIndex},
+    Rng,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt::Debug};
+use strum::IntoEnumIterator;
+use strum_macros::Display;
+use strum_macros::EnumIter;
+
+use crate::table::{goal_pair_from_index, goal_pair_index, NUM_GOAL_PAIRS};
+
+/// A board state together with both players' goals.  This is a node of the
+/// full game, as seen by an observer who knows both goals.
+#[derive(Serialize, Deserialize, Eq, PartialEq, Hash, Debug, Clone, Copy)]
+pub struct MetaState {
+    pub state: StateId,
+    pub p1goal: Outcome,
+    pub p2goal: Outcome,
+}
+
+impl MetaState {
+    /// The information state seen by the player to move.
+    pub fn info_state(&self, tree: &GameTree) -> InfoState {
+        InfoState {
+            state: self.state,
+            goal: if tree.current_player[self.state] == Player::Player1 {
+                self.p1goal
+            } else {
+                self.p2goal
+            },
+        }
+    }
+
+    /// The metastates reachable in one move, in the order of `tree.children`.
+    pub fn children(&self, tree: &GameTree) -> Vec<MetaState> {
+        tree.children[self.state]
+            .iter()
+            .map(|s| MetaState {
+                state: *s,
+                p1goal: self.p1goal,
+                p2goal: self.p2goal,
+            })
+            .collect()
+    }
+
+    pub fn parent(&self, tree: &GameTree) -> Option<MetaState> {
+        tree.parents[self.state].map(|s| MetaState {
+            state: s,
+            p1goal: self.p1goal,
+            p2goal: self.p2goal,
+        })
+    }
+    /// For terminal states, whether the first and second player achieved their
+    /// goals.  `None` if the round is not over.
+    pub fn outcomes(&self, tree: &GameTree) -> Option<(bool, bool)> {
+        tree.terminals[self.state]
+            .map(|outcome| (self.p1goal == outcome, self.p2goal == outcome.reverse()))
+    }
+}
+
+/// A board state together with the goal of the player to move, i.e. what
+/// that player actually knows.
+#[derive(Serialize, Deserialize, Eq, PartialEq, Hash, Debug, Clone, Copy)]
+pub struct InfoState {
+    pub state: StateId,
+    pub goal: Outcome,
+}
+
+/// Payoffs to the first player for each combination of players scoring.
+/// In a multi-round match these are the values of the subgames the round
+/// leads to.
+#[derive(Debug)]
+pub struct OutcomeValues {
+    pub both_win: f64,
+    pub p1_win: f64,
+    pub p2_win: f64,
+    pub both_lose: f64,
+    // Epsilon reward for making "smaller" move numbers.
+    pub first_move_epsilon: f64,
+}
+
+impl Default for OutcomeValues {
+    /// Payoffs for a single round played on its own: +1 if only the first
+    /// player scores, -1 if only the second player does, 0 otherwise.
+    fn default() -> OutcomeValues {
+        OutcomeValues {
+            both_win: 0f64,
+            p1_win: 1f64,
+            p2_win: -1f64,
+            both_lose: 0f64,
+            first_move_epsilon: 0f64,
+        }
+    }
+}
+
+impl OutcomeValues {
+    /// The first player's payoff for reaching terminal `state` with `outcomes`.
+    pub fn evaluate(&self, state: &MetaState, tree: &GameTree, outcomes: (bool, bool)) -> f64 {
+        let mut result = match outcomes {
+            (true, true) => self.both_win,
+            (true, false) => self.p1_win,
+            (false, true) => self.p2_win,
+            (false, false) => self.both_lose,
+        };
+
+        if self.first_move_epsilon != 0.0 {
+            let (p1movesum, p2movesum) = tree.states[state.state].move_sums();
+            result += self.first_move_epsilon * (p2movesum - p1movesum);
+        }
+        result
+    }
+}
+
+/// The result of a round from the first player's point of view, which is
+/// also what a player's secret goal is drawn from.
+#[derive(Serialize, Deserialize, Eq, PartialEq, Hash, Clone, Copy, EnumIter, Debug, Display)]
+pub enum Outcome {
+    Win,
+    Lose,
+    Tie,
+}
+
+impl Outcome {
+    /// Position in `Outcome::iter()`, used to index tables.
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    /// The same result from the other player's point of view.
+    pub fn reverse(&self) -> Outcome {
+        match self {
+            Outcome::Win => Outcome::Lose,
+            Outcome::Lose => Outcome::Win,
+            Outcome::Tie => Outcome::Tie,
+        }
+    }
+}
+
+impl Distribution<Outcome> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Outcome {
+        match rng.gen_range(0..=2) {
+            // rand 0.8
+            0 => Outcome::Win,
+            1 => Outcome::Lose,
+            _ => Outcome::Tie,
+        }
+    }
+}
+
+/// How the goals are dealt at the start of a round, as a joint distribution
+/// over (first player's goal, second player's goal) so the goals can be
+/// correlated.
+///
+/// Stored as unnormalized weights in goal-pair order.  The uniform deal has
+/// every weight 1, so that probabilities and averages come out exactly as
+/// `1 / 9` and `sum / 9`.
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+pub struct GoalDistribution {
+    weights: [f64; NUM_GOAL_PAIRS],
+}
+
+impl Default for GoalDistribution {
+    fn default() -> Self {
+        Self::uniform()
+    }
+}
+
+impl GoalDistribution {
+    /// Both goals are independent and uniform, the usual game.
+    pub fn uniform() -> GoalDistribution {
+        GoalDistribution {
+            weights: [1.0; NUM_GOAL_PAIRS],
+        }
+    }
+
+    /// Weights for each goal pair, indexed by [`goal_pair_index`].  Fails if
+    /// any weight is negative or they are all zero.
+    pub fn joint(weights: [f64; NUM_GOAL_PAIRS]) -> Result<GoalDistribution, String> {
+        if weights.iter().any(|w| !w.is_finite() || *w < 0.0) {
+            return Err(format!(
+                "goal weights must be non-negative, got {:?}",
+                weights
+            ));
+        }
+        if weights.iter().sum::<f64>() <= 0.0 {
+            return Err("goal weights must not all be zero".to_string());
+        }
+        Ok(GoalDistribution { weights })
+    }
+
+    /// Each goal is drawn independently, `weights` being indexed by
+    /// `Outcome::index()`.
+    pub fn marginal(weights: [f64; 3]) -> Result<GoalDistribution, String> {
+        let mut joint = [0.0; NUM_GOAL_PAIRS];
+        for p1goal in Outcome::iter() {
+            for p2goal in Outcome::iter() {
+                joint[goal_pair_index(p1goal, p2goal)] =
+                    weights[p1goal.index()] * weights[p2goal.index()];
+            }
+        }
+        GoalDistribution::joint(joint)
+    }
+
+    /// Uniform over the goal pairs where the two goals differ.
+    pub fn distinct() -> GoalDistribution {
+        let mut weights = [1.0; NUM_GOAL_PAIRS];
+        for goal in Outcome::iter() {
+            weights[goal_pair_index(goal, goal)] = 0.0;
+        }
+        GoalDistribution { weights }
+    }
+
+    /// The unnormalized weight of each goal pair, indexed by
+    /// [`goal_pair_index`].
+    pub fn weights(&self) -> &[f64; NUM_GOAL_PAIRS] {
+        &self.weights
+    }
+
+    fn total_weight(&self) -> f64 {
+        self.weights.iter().sum()
+    }
+
+    /// The probability of dealing this pair of goals.
+    pub fn prob(&self, p1goal: Outcome, p2goal: Outcome) -> f64 {
+        self.weights[goal_pair_index(p1goal, p2goal)] / self.total_weight()
+    }
+
+    /// The expectation of `value` over the deal.
+    pub fn expectation(&self, value: impl Fn(Outcome, Outcome) -> f64) -> f64 {
+        let mut total = 0.0;
+        for p1goal in Outcome::iter() {
+            for p2goal in Outcome::iter() {
+                total += self.weights[goal_pair_index(p1goal, p2goal)] * value(p1goal, p2goal);
+            }
+        }
+        total / self.total_weight()
+    }
+
+    /// Deal a pair of goals.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> (Outcome, Outcome) {
+        let index = WeightedIndex::new(self.weights).unwrap().sample(rng);
+        goal_pair_from_index(index)
+    }
+}
+
+impl std::fmt::Display for GoalDistribution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if *self == GoalDistribution::uniform() {
+            return write!(f, "uniform");
+        }
+        if *self == GoalDistribution::distinct() {
+            return write!(f, "distinct");
+        }
+        let weights: Vec<String> = self.weights.iter().map(|w| w.to_string()).collect();
+        write!(f, "joint:{}", weights.join(","))
+    }
+}
+
+impl std::str::FromStr for GoalDistribution {
+    type Err = String;
+
+    /// Parses `uniform`, `distinct` (the goals are never equal),
+    /// `marginal:W,L,T` (independent goals with these weights) or
+    /// `joint:WW,WL,WT,LW,LL,LT,TW,TL,TT` (weights for each pair, the first
+    /// player's goal first).
+    fn from_str(s: &str) -> Result<GoalDistribution, String> {
+        let error = || {
+            format!(
+                "invalid goal distribution {:?}, expected uniform, distinct, \
+                 marginal:W,L,T or joint: followed by 9 weights",
+                s
+            )
+        };
+        let weights = |list: &str| -> Result<Vec<f64>, String> {
+            list.split(',')
+                .map(|w| w.trim().parse().map_err(|_| error()))
+                .collect()
+        };
+        match s.split_once(':') {
+            None if s == "uniform" => Ok(GoalDistribution::uniform()),
+            None if s == "distinct" => Ok(GoalDistribution::distinct()),
+            Some(("marginal", list)) => {
+                GoalDistribution::marginal(weights(list)?.try_into().map_err(|_| error())?)
+            }
+            Some(("joint", list)) => {
+                GoalDistribution::joint(weights(list)?.try_into().map_err(|_| error())?)
+            }
+            _ => Err(error()),
+        }
+    }
+}
+
+/// Index of a state in [`GameTree::states`].
+pub type StateId = usize;
+
+/// Every reachable board, up to symmetry, with the edges between them.
+/// States whose outcome is already forced are made terminal.
+pub struct GameTree {
+    pub board: Board,
+    /// How goals are dealt at the start of each round.
+    pub goals: GoalDistribution,
+    // Topologically sorted
+    pub states: Vec<State>,
+    pub ids: HashMap<State, StateId>,
+    pub parents: Vec<Option<StateId>>,
+    pub children: Vec<Vec<StateId>>,
+    pub terminals: Vec<Option<Outcome>>,
+    pub current_player: Vec<Player>,
+    /// The moves out of state `s` are numbered `action_offsets[s]` up to
+    /// `action_offsets[s + 1]` across the whole tree.
+    pub action_offsets: Vec<usize>,
+}
+
+impl Default for GameTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GameTree {
+    /// The tree for the usual 3x3 board.
+    pub fn new() -> GameTree {
+        GameTree::with_board(Board::default())
+    }
+
+    pub fn with_board(board: Board) -> GameTree {
+        // Only states whose outcome isn't forced yet are expanded.  The
+        // outcome only depends on the position, so it is worked out once per
+        // position up to symmetry rather than for every move order.
+        let mut forced = HashMap::new();
+        let mut all_states = vec![];
+        let mut terminals = vec![];
+        let mut stack = vec![State::start(board)];
+        while let Some(state) = stack.pop() {
+            let outcome = forced_outcome(&state.drop_history(), &mut forced);
+            all_states.push(state);
+            terminals.push(outcome);
+            if outcome.is_none() {
+                // Reversed so the children come off the stack in order, which
+                // keeps the states in the same depth first order as always.
+                stack.extend(state.children().into_iter().rev());
+            }
+        }
+        let ids: HashMap<State, StateId> = all_states
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (*s, i))
+            .collect();
+
+        let mut parents = vec![None; all_states.len()];
+        let mut children = vec![Vec::new(); all_states.len()];
+        let mut action_offsets = vec![0];
+        for (id, state) in all_states.iter().enumerate() {
+            if terminals[id].is_none() {
+                for child in state.children() {
+                    let child_id = ids[&child];
+                    parents[child_id] = Some(id);
+                    children[id].push(child_id);
+                }
+            }
+            action_offsets.push(action_offsets[id] + children[id].len());
+        }
+        let current_player = all_states.iter().map(|s| s.current_player()).collect();
+
+        GameTree {
+            board,
+            goals: GoalDistribution::default(),
+            states: all_states,
+            ids,
+            parents,
+            children,
+            terminals,
+            current_player,
+            action_offsets,
+        }
+    }
+
+    /// The same tree with goals dealt from `goals`.
+    pub fn with_goals(self, goals: GoalDistribution) -> GameTree {
+        GameTree { goals, ..self }
+    }
+
+    pub fn num_terminals(&self) -> usize {
+        self.terminals.iter().flatten().count()
+    }
+
+    /// A hash of the states, moves, outcomes and goal distribution, which
+    /// saved solutions record so they are only loaded for the tree they were
+    /// solved on.  Unlike `std`'s hashers it doesn't change between Rust
+    /// versions.
+    pub fn fingerprint(&self) -> u64 {
+        // 64 bit FNV-1a.
+        let mut hash: u64 = 0xcbf29ce484222325;
+        let mut add = |bytes: &[u8]| {
+            for byte in bytes {
+                hash ^= *byte as u64;
+                hash = hash.wrapping_mul(0x100000001b3);
+            }
+        };
+        add(&[self.board.rows, self.board.cols, self.board.k]);
+        for state in 0..self.states.len() {
+            add(self.states[state].cells());
+            add(&[self.terminals[state].map_or(3, |o| o.index() as u8)]);
+            add(&(self.children[state].len() as u64).to_le_bytes());
+            for &child in self.children[state].iter() {
+                add(&(child as u64).to_le_bytes());
+            }
+        }
+        for p1goal in Outcome::iter() {
+            for p2goal in Outcome::iter() {
+                add(&self.goals.prob(p1goal, p2goal).to_le_bytes());
+            }
+        }
+        hash
+    }
+}
+
+/// The outcome of the round from `position`, a state without move order, if
+/// it no longer depends on the players' choices.  `memo` holds the outcomes
+/// found so far, by [`State::canonical`] position.
+fn forced_outcome(position: &State, memo: &mut HashMap<State, Option<Outcome>>) -> Option<Outcome> {
+    if let Some(outcome) = position.outcome() {
+        return Some(outcome);
+    }
+    let canonical = position.canonical();
+    if let Some(&outcome) = memo.get(&canonical) {
+        return outcome;
+    }
+    let marks = position.cells().iter().filter(|&&m| m != 0).count();
+    let mark = if marks % 2 == 0 { 1 } else { 2 };
+    let mut outcome = None;
+    for i in 0..position.board.num_cells() {
+        if position.moves[i] != 0 {
+            continue;
+        }
+        let mut child = *position;
+        child.moves[i] = mark;
+        let child_outcome = forced_outcome(&child, memo);
+        if child_outcome.is_none() || (outcome.is_some() && child_outcome != outcome) {
+            outcome = None;
+            break;
+        }
+        outcome = child_outcome;
+    }
+    memo.insert(canonical, outcome);
+    outcome
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Hash, Eq, PartialEq, EnumIter)]
+pub enum Player {
+    Player1,
+    Player2,
+}
+
+impl Player {
+    pub fn opponent(&self) -> Player {
+        match self {
+            Player::Player1 => Player::Player2,
+            Player::Player2 => Player::Player1,
+        }
+    }
+}
+/// The largest number of squares a [`Board`] may have.
+pub const MAX_CELLS: usize = 16;
+
+/// The shape of the board, and how many marks in a row win.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Board {
+    pub rows: u8,
+    pub cols: u8,
+    pub k: u8,
+}
+
+impl Default for Board {
+    /// The 3x3 board with three in a row from the comic.
+    fn default() -> Board {
+        Board {
+            rows: 3,
+            cols: 3,
+            k: 3,
+        }
+    }
+}
+
+impl Board {
+    pub fn new(rows: u8, cols: u8, k: u8) -> Result<Board, String> {
+        if rows == 0 || cols == 0 {
+            return Err("the board must have at least one row and column".to_string());
+        }
+        if rows as usize * cols as usize > MAX_CELLS {
+            return Err(format!("boards may have at most {} squares", MAX_CELLS));
+        }
+        if k == 0 || k > rows.max(cols) {
+            return Err(format!(
+                "{} in a row doesn't fit on a {}x{} board",
+                k, rows, cols
+            ));
+        }
+        Ok(Board { rows, cols, k })
+    }
+
+    pub fn num_cells(&self) -> usize {
+        self.rows as usize * self.cols as usize
+    }
+
+    /// Every set of `k` squares in a row, column or diagonal, as indices into
+    /// [`State::moves`].
+    pub fn lines(&self) -> Vec<Vec<usize>> {
+        let (rows, cols, k) = (self.rows as i32, self.cols as i32, self.k as i32);
+        let mut result = vec![];
+        for (dx, dy) in [(0, 1), (1, 0), (1, 1), (1, -1)] {
+            for x in 0..rows {
+                for y in 0..cols {
+                    let (end_x, end_y) = (x + dx * (k - 1), y + dy * (k - 1));
+                    if (0..rows).contains(&end_x) && (0..cols).contains(&end_y) {
+                        result.push(
+                            (0..k)
+                                .map(|i| ((x + dx * i) * cols + y + dy * i) as usize)
+                                .collect(),
+                        );
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// The symmetries of the board, numbered as in [`State::rotate`].  A
+    /// square board has eight, other boards only four.
+    pub fn symmetries(&self) -> Vec<u8> {
+        if self.rows == self.cols {
+            (0..8).collect()
+        } else {
+            vec![0, 2, 4, 6]
+        }
+    }
+}
+
+impl std::fmt::Display for Board {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{}:{}", self.rows, self.cols, self.k)
+    }
+}
+
+impl std::str::FromStr for Board {
+    type Err = String;
+
+    /// Parses `ROWSxCOLS:K`, e.g. `4x4:3`.  Without `:K` a full row or column
+    /// is needed, e.g. `3x3` is the usual game.
+    fn from_str(s: &str) -> Result<Board, String> {
+        let error = || format!("invalid board {:?}, expected e.g. 3x3 or 4x4:3", s);
+        let (size, k) = match s.split_once(':') {
+            Some((size, k)) => (size, Some(k)),
+            None => (s, None),
+        };
+        let (rows, cols) = size.split_once('x').ok_or_else(error)?;
+        let rows: u8 = rows.trim().parse().map_err(|_| error())?;
+        let cols: u8 = cols.trim().parse().map_err(|_| error())?;
+        let k = match k {
+            Some(k) => k.trim().parse().map_err(|_| error())?,
+            None => rows.min(cols),
+        };
+        Board::new(rows, cols, k)
+    }
+}
+
+/// A board, where each square holds the move number it was played on (or
+/// 0 if empty).  Odd moves belong to the first player.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct State {
+    pub board: Board,
+    /// Squares in row-major order.  Only the first `board.num_cells()` are
+    /// used.
+    pub moves: [u8; MAX_CELLS],
+}
+
+fn fmt_digit(f: &mut std::fmt::Formatter<'_>, digit: u8) -> std::fmt::Result {
+    if digit == 0 {
+        f.write_fmt(format_args!(". "))
+    } else if digit.is_multiple_of(2) {
+        f.write_fmt(format_args!("\x1b[31m{}\x1b[0m ", digit))
+    } else {
+        f.write_fmt(format_args!("\x1b[32m{}\x1b[0m ", digit))
+    }
+}
+
+impl Debug for State {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let cols = self.board.cols as usize;
+        for i in 0..self.board.num_cells() {
+            fmt_digit(f, self.moves[i])?;
+            if i % cols == cols - 1 {
+                f.write_fmt(format_args!("\n"))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl State {
+    pub fn start(board: Board) -> Self {
+        State {
+            board,
+            moves: [0; MAX_CELLS],
+        }
+    }
+
+    /// The squares of the board, without the unused padding.
+    pub fn cells(&self) -> &[u8] {
+        &self.moves[..self.board.num_cells()]
+    }
+
+    pub fn current_player(&self) -> Player {
+        let max = self.moves.iter().max().unwrap();
+        if max.is_multiple_of(2) {
+            Player::Player1
+        } else {
+            Player::Player2
+        }
+    }
+    pub fn is_final(&self) -> bool {
+        self.outcome().is_some()
+    }
+
+    pub fn outcome(&self) -> Option<Outcome> {
+        for line in self.board.lines() {
+            if line
+                .iter()
+                .all(|&i| self.moves[i] != 0 && (self.moves[i] & 1) != 0)
+            {
+                return Some(Outcome::Win);
+            }
+            if line
+                .iter()
+                .all(|&i| self.moves[i] != 0 && (self.moves[i] & 1) == 0)
+            {
+                return Some(Outcome::Lose);
+            }
+        }
+        if self.cells().iter().all(|&m| m != 0) {
+            return Some(Outcome::Tie);
+        }
+        None
+    }
+
+    pub fn children(&self) -> Vec<State> {
+        let mut result = vec![];
+        if self.outcome().is_none() {
+            let move_num = self.moves.into_iter().max().unwrap();
+            for i in 0..self.board.num_cells() {
+                if self.moves[i] == 0 {
+                    let mut clone = *self;
+                    clone.moves[i] = move_num + 1;
+                    // if clone.moves[i] == 3 {
+                    //     clone.moves[i] = 1;
+                    // }
+                    if !result
+                        .iter()
+                        .any(|s: &State| clone.drop_history().is_symmetry(&s.drop_history()))
+                    {
+                        result.push(clone);
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// The same board with move order forgotten: 1 for the first player, 2 for
+    /// the second.
+    pub fn drop_history(&self) -> State {
+        let mut result = *self;
+        for m in result.moves.iter_mut() {
+            if *m != 0 {
+                *m = (*m - 1) % 2 + 1;
+            }
+        }
+        result
+    }
+    /// Apply one of the eight symmetries of the square, numbered 0 to 7.  Only
+    /// the ones listed by [`Board::symmetries`] may be used on boards that
+    /// aren't square.
+    pub fn rotate(&self, symmetry: u8) -> State {
+        let (rows, cols) = (self.board.rows as usize, self.board.cols as usize);
+        let (n, m) = (rows - 1, cols - 1);
+        let mut moves = [0u8; MAX_CELLS];
+        for x in 0..rows {
+            for y in 0..cols {
+                let (from_x, from_y) = match symmetry {
+                    0 => (x, y),
+                    1 => (y, x),
+                    2 => (n - x, y),
+                    3 => (n - y, x),
+                    4 => (x, m - y),
+                    5 => (y, m - x),
+                    6 => (n - x, m - y),
+                    7 => (n - y, m - x),
+                    _ => unreachable!("there are only 8 symmetries, not {}", symmetry),
+                };
+                moves[x * cols + y] = self.moves[from_x * cols + from_y];
+            }
+        }
+        State {
+            board: self.board,
+            moves,
+        }
+    }
+
+    /// The same state under whichever symmetry of the board makes its
+    /// squares smallest, so symmetric states have the same canonical state.
+    pub fn canonical(&self) -> State {
+        self.board
+            .symmetries()
+            .into_iter()
+            .map(|i| self.rotate(i))
+            .min_by_key(|s| s.moves)
+            .unwrap()
+    }
+
+    pub fn is_symmetry(&self, other: &State) -> bool {
+        self.symmetry_from(other).is_some()
+    }
+
+    /// A symmetry taking `other` to this state, so that
+    /// `other.rotate(symmetry) == *self`.
+    pub fn symmetry_from(&self, other: &State) -> Option<u8> {
+        self.board
+            .symmetries()
+            .into_iter()
+            .find(|&i| self == &other.rotate(i))
+    }
+
+    /// The square of the most recent move, or `None` on an empty board.
+    pub fn last_move(&self) -> Option<usize> {
+        let cells = self.cells();
+        let latest = cells.iter().copied().max().filter(|&m| m != 0)?;
+        cells.iter().position(|&m| m == latest)
+    }
+
+    /// How far from the top left each player's moves are, weighting earlier
+    /// moves more.  Move `m` on square `i` counts `i * num_cells^(num_cells -
+    /// 1 - m)`, so the last move on a full board counts a fraction of `i`.
+    pub fn move_sums(&self) -> (f64, f64) {
+        let num_cells = self.board.num_cells() as i32;
+        let (mut p1sum, mut p2sum) = (0.0, 0.0);
+        for (i, &m) in self.cells().iter().enumerate() {
+            if m != 0 {
+                let weight = (num_cells as f64).powi(num_cells - 1 - m as i32) * i as f64;
+                if m % 2 == 1 {
+                    p1sum += weight;
+                } else {
+                    p2sum += weight;
+                }
+            }
+        }
+        (p1sum, p2sum)
+    }
+}
+
+/// A round as a human sees it.  The game tree keeps one state out of each
+/// set of symmetric ones, so following it would rotate or flip the board
+/// under the player; this follows the tree while showing the board in the
+/// orientation the human has been playing in.
+#[derive(Clone, Copy, Debug)]
+pub struct DisplayedState {
+    state: StateId,
+    /// The board as the human sees it, with the moves in the order they were
+    /// played.
+    pub view: State,
+    /// The symmetry taking the tree's state to `view`, ignoring move order.
+    symmetry: u8,
+}
+
+impl DisplayedState {
+    /// The start of a round.
+    pub fn start(tree: &GameTree) -> DisplayedState {
+        DisplayedState {
+            state: 0,
+            view: tree.states[0],
+            symmetry: 0,
+        }
+    }
+
+    /// The state in the game tree.
+    pub fn state(&self) -> StateId {
+        self.state
+    }
+
+    /// Play on `square` of the displayed board, counting from 0 in row-major
+    /// order, and return the new state in the tree.  Returns `None`, leaving
+    /// the round unchanged, if that isn't a legal move.
+    pub fn play_square(&mut self, tree: &GameTree, square: usize) -> Option<StateId> {
+        if square >= self.view.board.num_cells() || self.view.moves[square] != 0 {
+            return None;
+        }
+        let mut view = self.view;
+        view.moves[square] = view.moves.iter().max().unwrap() + 1;
+        for &child in tree.children[self.state].iter() {
+            if let Some(symmetry) = view
+                .drop_history()
+                .symmetry_from(&tree.states[child].drop_history())
+            {
+                *self = DisplayedState {
+                    state: child,
+                    view,
+                    symmetry,
+                };
+                return Some(child);
+            }
+        }
+        None
+    }
+
+    /// Play the tree's move `index` out of the current state, and return the
+    /// new state in the tree.
+    pub fn play_child(&mut self, tree: &GameTree, index: usize) -> StateId {
+        let child = tree.children[self.state][index];
+        let square = tree.states[child]
+            .rotate(self.symmetry)
+            .last_move()
+            .unwrap();
+        self.view.moves[square] = self.view.moves.iter().max().unwrap() + 1;
+        self.state = child;
+        child
+    }
+}
+
+/// The score before a round of a multi-round match, with `p1score` being
+/// the score of whoever moves first this round.
+#[derive(Serialize, Deserialize, Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Default)]
+pub struct Subgame {
+    pub p1score: i8,
+    pub p2score: i8,
+    /// The number of rounds played so far, for match formats where it
+    /// matters, and 0 otherwise.
+    #[serde(default)]
+    pub round: i8,
+}
+
+impl std::fmt::Display for Subgame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {})", self.p1score, self.p2score)?;
+        if self.round != 0 {
+            write!(f, " in round {}", self.round + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Subgame {
+    type Err = String;
+
+    /// Parses `P1SCORE,P2SCORE`, e.g. `2,1`, or `P1SCORE,P2SCORE,ROUND` for
+    /// match formats that count rounds.
+    fn from_str(s: &str) -> Result<Subgame, String> {
+        let error = || format!("invalid subgame {:?}, expected e.g. 2,1", s);
+        let parts: Vec<i8> = s
+            .split(',')
+            .map(|part| part.trim().parse().map_err(|_| error()))
+            .collect::<Result<_, _>>()?;
+        match parts[..] {
+            [p1score, p2score] => Ok(Subgame {
+                p1score,
+                p2score,
+                round: 0,
+            }),
+            [p1score, p2score, round] => Ok(Subgame {
+                p1score,
+                p2score,
+                round,
+            }),
+            _ => Err(error()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(board: Board, cells: &[u8]) -> State {
+        let mut state = State::start(board);
+        state.moves[..cells.len()].copy_from_slice(cells);
+        state
+    }
+
+    #[test]
+    fn board_parsing() {
+        assert_eq!("3x3".parse(), Ok(Board::default()));
+        assert_eq!("3x4:3".parse(), Board::new(3, 4, 3));
+        assert_eq!("4x2".parse(), Board::new(4, 2, 2));
+        assert_eq!("2 x 4 : 3".parse(), Board::new(2, 4, 3));
+        for invalid in ["3", "3x", "x3", "3by3", "3x3:", "3x3:a", "-1x3"] {
+            assert!(invalid.parse::<Board>().is_err(), "{}", invalid);
+        }
+        // Too many squares, a line that doesn't fit, and an empty board.
+        for invalid in ["5x4", "3x3:4", "2x4:5", "0x3", "3x3:0"] {
+            assert!(invalid.parse::<Board>().is_err(), "{}", invalid);
+        }
+        let board = Board::new(2, 4, 3).unwrap();
+        assert_eq!(board.to_string().parse(), Ok(board));
+    }
+
+    #[test]
+    fn lines_on_a_non_square_board() {
+        // 3x4 with three in a row: two in each row, one in each column and
+        // two in each diagonal direction.
+        let board: Board = "3x4:3".parse().unwrap();
+        let lines = board.lines();
+        assert_eq!(lines.len(), 3 * 2 + 4 + 2 * 2);
+        assert!(lines.contains(&vec![1, 2, 3]));
+        assert!(lines.contains(&vec![3, 7, 11]));
+        assert!(lines.contains(&vec![1, 6, 11]));
+        assert!(lines.contains(&vec![3, 6, 9]));
+        assert!(!lines.contains(&vec![0, 5, 10, 15]));
+    }
+
+    #[test]
+    fn rotation_on_a_non_square_board() {
+        let board: Board = "2x3".parse().unwrap();
+        assert_eq!(board.symmetries(), vec![0, 2, 4, 6]);
+        // 1 2 .
+        // . . 3
+        let s = state(board, &[1, 2, 0, 0, 0, 3]);
+        assert_eq!(s.rotate(0), s);
+        assert_eq!(s.rotate(2).cells(), &[0, 0, 3, 1, 2, 0]);
+        assert_eq!(s.rotate(4).cells(), &[0, 2, 1, 3, 0, 0]);
+        assert_eq!(s.rotate(6).cells(), &[3, 0, 0, 0, 2, 1]);
+        assert_eq!(s.rotate(2).rotate(4), s.rotate(6));
+        for symmetry in board.symmetries() {
+            let rotated = s.rotate(symmetry);
+            // Each of these flips is its own inverse.
+            assert_eq!(rotated.rotate(symmetry), s);
+            assert_eq!(rotated.symmetry_from(&s), Some(symmetry));
+            assert_eq!(rotated.canonical(), s.canonical());
+            assert_eq!(rotated.outcome(), s.outcome());
+        }
+        // The last move stays the same square up to the flip.
+        assert_eq!(s.last_move(), Some(5));
+        assert_eq!(s.rotate(6).last_move(), Some(0));
+    }
+
+    #[test]
+    fn goal_distribution_parsing() {
+        assert_eq!("uniform".parse(), Ok(GoalDistribution::uniform()));
+        assert_eq!("distinct".parse(), Ok(GoalDistribution::distinct()));
+
+        let marginal: GoalDistribution = "marginal:2, 1,0".parse().unwrap();
+        assert_eq!(
+            marginal.weights(),
+            &[4.0, 2.0, 0.0, 2.0, 1.0, 0.0, 0.0, 0.0, 0.0]
+        );
+        assert_eq!(marginal.prob(Outcome::Win, Outcome::Lose), 2.0 / 9.0);
+        assert_eq!(marginal.prob(Outcome::Tie, Outcome::Win), 0.0);
+
+        let joint: GoalDistribution = "joint:0,1,0,0,0,0,0,0,3".parse().unwrap();
+        assert_eq!(joint.prob(Outcome::Win, Outcome::Lose), 0.25);
+        assert_eq!(joint.prob(Outcome::Tie, Outcome::Tie), 0.75);
+        assert_eq!(joint.expectation(|p1goal, _| p1goal.index() as f64), 1.5);
+
+        // Display writes what parses back to the same distribution.
+        for goals in [
+            GoalDistribution::uniform(),
+            GoalDistribution::distinct(),
+            marginal,
+            joint,
+        ] {
+            assert_eq!(goals.to_string().parse(), Ok(goals));
+        }
+        assert_eq!(joint.to_string(), "joint:0,1,0,0,0,0,0,0,3");
+
+        for invalid in [
+            "",
+            "uniform:1",
+            "normal",
+            "marginal:1,1",
+            "marginal:1,1,1,1",
+            "marginal:1,x,1",
+            "marginal:1,-1,1",
+            "joint:1,1,1,1,1,1,1,1",
+            "joint:0,0,0,0,0,0,0,0,0",
+            "joint:1,1,1,1,1,1,1,1,inf",
+        ] {
+            assert!(invalid.parse::<GoalDistribution>().is_err(), "{}", invalid);
+        }
+    }
+}
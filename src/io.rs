@@ -0,0 +1,449 @@
+//! Reading and writing solver artifacts.
+//!
+//! Solution directories hold a few files per saved iteration:
+//! `strategy_{N}.bincode` is the current [`Strategy`](crate::Strategy) at that
+//! iteration, `average_strategy_{N}.bincode` is the average strategy and
+//! `debug_{N}.bincode` is the full [`CFR`](crate::CFR) state.  Only the first
+//! is required; older solutions such as the shipped `solution_1e4/` have
+//! nothing else.  A multi-round solution has one such directory per subgame,
+//! named `subgame_{p1}_{p2}`, and while it is being solved a
+//! `checkpoint.bincode` holding the whole [`MultiroundCheckpoint`].
+//!
+//! The solvers also write a `manifest.json` into each directory, a
+//! [`Manifest`] saying how the solution was made and which game tree it
+//! belongs to.  Solutions without one are still loaded, as long as their
+//! strategies fit the tree.
+//!
+//! Where a solver measures the exploitability of a saved iteration it also
+//! writes an `exploitability_{N}.json`, the
+//! [`ExploitabilityReport`](crate::ExploitabilityReport) of its average
+//! strategy.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::game::{Board, GameTree, GoalDistribution, OutcomeValues, Subgame};
+use crate::match_format::MatchFormat;
+use crate::solver::{CFRVariant, CFR};
+use crate::strategy::Strategy;
+
+/// Serialize `value` with bincode into a new file at `path`.
+pub fn save<T: Serialize>(path: &Path, value: &T) -> bincode::Result<()> {
+    let file = BufWriter::new(File::create(path)?);
+    bincode::serialize_into(file, value)
+}
+
+/// Like [`save`], but writes to a temporary file first and renames it into
+/// place, so `path` never holds a partially written file.
+pub fn save_atomic<T: Serialize>(path: &Path, value: &T) -> bincode::Result<()> {
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    let temp_path = PathBuf::from(temp_path);
+    save(&temp_path, value)?;
+    fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+/// Write `value` as JSON to `path`, through a temporary file like
+/// [`save_atomic`].
+pub fn save_json<T: Serialize>(path: &Path, value: &T) -> bincode::Result<()> {
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    let mut file = BufWriter::new(File::create(&temp_path)?);
+    serde_json::to_writer_pretty(&mut file, value).map_err(|e| custom_error(e.to_string()))?;
+    file.flush()?;
+    fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+/// Deserialize a bincode file written by [`save`].
+pub fn load<T: DeserializeOwned>(path: &Path) -> bincode::Result<T> {
+    let file = BufReader::new(File::open(path)?);
+    bincode::deserialize_from(file)
+}
+
+pub fn debug_path(dir: &Path, iteration: usize) -> PathBuf {
+    dir.join(format!("debug_{}.bincode", iteration))
+}
+
+pub fn strategy_path(dir: &Path, iteration: usize) -> PathBuf {
+    dir.join(format!("strategy_{}.bincode", iteration))
+}
+
+pub fn average_strategy_path(dir: &Path, iteration: usize) -> PathBuf {
+    dir.join(format!("average_strategy_{}.bincode", iteration))
+}
+
+pub fn best_response_path(dir: &Path, iteration: usize) -> PathBuf {
+    dir.join(format!("best_response_{}.bincode", iteration))
+}
+
+pub fn exploitability_report_path(dir: &Path, iteration: usize) -> PathBuf {
+    dir.join(format!("exploitability_{}.json", iteration))
+}
+
+pub fn subgame_dir(dir: &Path, subgame: &Subgame) -> PathBuf {
+    if subgame.round != 0 {
+        return dir.join(format!(
+            "subgame_{}_{}_round_{}",
+            subgame.p1score, subgame.p2score, subgame.round
+        ));
+    }
+    dir.join(format!("subgame_{}_{}", subgame.p1score, subgame.p2score))
+}
+
+pub fn checkpoint_path(dir: &Path) -> PathBuf {
+    dir.join("checkpoint.bincode")
+}
+
+pub fn manifest_path(dir: &Path) -> PathBuf {
+    dir.join("manifest.json")
+}
+
+/// The version of the solution directory layout written by this crate.
+/// Version 0 is the layout without manifests.
+pub const FORMAT_VERSION: u32 = 1;
+
+fn custom_error(message: String) -> bincode::Error {
+    Box::new(bincode::ErrorKind::Custom(message))
+}
+
+/// How a solution directory was made.  Settings that the solver that wrote
+/// it doesn't have are `None`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Manifest {
+    pub format_version: u32,
+    /// The program that wrote the solution, e.g. `solve`.
+    pub solver: String,
+    pub board: String,
+    pub goals: String,
+    /// [`GameTree::fingerprint`] of the tree it was solved on, in hex.
+    pub tree_hash: String,
+    pub variant: Option<CFRVariant>,
+    pub alternate_updates: Option<bool>,
+    pub small_move_epsilon: Option<f64>,
+    pub small_move_epsilon_decay: Option<f64>,
+    /// The rules of the match, as written for `--format`.
+    pub match_format: Option<String>,
+    /// The subgame of a match this directory solves.
+    pub subgame: Option<Subgame>,
+    /// The last iteration saved so far.
+    pub last_iteration: Option<usize>,
+    /// The exploitability bound of the average strategy at the last
+    /// iteration, if it was measured.
+    pub exploitability: Option<f64>,
+}
+
+impl Manifest {
+    /// A manifest for a solution of `tree` by `solver`, with no settings.
+    pub fn new(solver: &str, tree: &GameTree) -> Manifest {
+        Manifest {
+            format_version: FORMAT_VERSION,
+            solver: solver.to_string(),
+            board: tree.board.to_string(),
+            goals: tree.goals.to_string(),
+            tree_hash: format!("{:016x}", tree.fingerprint()),
+            variant: None,
+            alternate_updates: None,
+            small_move_epsilon: None,
+            small_move_epsilon_decay: None,
+            match_format: None,
+            subgame: None,
+            last_iteration: None,
+            exploitability: None,
+        }
+    }
+
+    /// Write the manifest into `dir`, replacing any older one.
+    pub fn save(&self, dir: &Path) -> bincode::Result<()> {
+        save_json(&manifest_path(dir), self)
+    }
+
+    /// Fails with a message for the user if the solution can't be used with
+    /// `tree`.
+    pub fn check(&self, tree: &GameTree) -> Result<(), String> {
+        if self.format_version > FORMAT_VERSION {
+            return Err(format!(
+                "it was written in format version {}, but this version of tictac only reads up to {}",
+                self.format_version, FORMAT_VERSION
+            ));
+        }
+        if self.tree_hash != format!("{:016x}", tree.fingerprint()) {
+            return Err(format!(
+                "it was solved on a different game tree, with --board {} --goals {}",
+                self.board, self.goals
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The manifest of `dir`, or `None` if it has none.
+pub fn load_manifest(dir: &Path) -> bincode::Result<Option<Manifest>> {
+    let path = manifest_path(dir);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let file = BufReader::new(File::open(&path)?);
+    let manifest = serde_json::from_reader(file)
+        .map_err(|e| custom_error(format!("couldn't read {}: {}", path.display(), e)))?;
+    Ok(Some(manifest))
+}
+
+/// The complete state of a multi-round solve, enough to continue it later
+/// with identical results.
+#[derive(Serialize, Deserialize)]
+pub struct MultiroundCheckpoint {
+    pub format: MatchFormat,
+    pub board: Board,
+    pub goals: GoalDistribution,
+    /// The [`GameTree::fingerprint`] of the tree the subgames are solved on.
+    pub tree_hash: u64,
+    /// The subgames are solved in the groups of
+    /// [`MatchFormat::solve_order`], one level at a time.  This is the level
+    /// being solved; the number of levels means every group is done.
+    pub level: usize,
+    /// The next iteration to run for the groups that have not converged yet.
+    pub iteration: i32,
+    /// Groups on `level` that have already converged, by their first
+    /// subgame.
+    pub converged: Vec<Subgame>,
+    pub solutions: HashMap<Subgame, CFR>,
+    pub strategies: HashMap<Subgame, Strategy>,
+    pub evs: HashMap<Subgame, f64>,
+}
+
+impl MultiroundCheckpoint {
+    /// A solve on `tree` that has not run any iterations yet.
+    pub fn new(format: MatchFormat, tree: &GameTree) -> MultiroundCheckpoint {
+        MultiroundCheckpoint {
+            format,
+            board: tree.board,
+            goals: tree.goals,
+            tree_hash: tree.fingerprint(),
+            level: 0,
+            iteration: 0,
+            converged: Vec::new(),
+            solutions: HashMap::new(),
+            strategies: HashMap::new(),
+            evs: HashMap::new(),
+        }
+    }
+
+    /// Fails with a message for the user, to follow the checkpoint's path,
+    /// if it wasn't made on `tree`.
+    pub fn check_tree(&self, tree: &GameTree) -> Result<(), String> {
+        if self.goals != tree.goals {
+            return Err(format!("was made with --goals {}", self.goals));
+        }
+        if self.board != tree.board || self.tree_hash != tree.fingerprint() {
+            return Err(format!(
+                "was made on a different game tree, with --board {}",
+                self.board
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Payoffs for the round played at the scores of `subgame`, given by the
+/// values of the subgames it leads to in the checkpoint of the `solve-match`
+/// run in `solutions_dir`, along with the match's format.  Fails with a
+/// message for the user if the checkpoint can't be read, was made on
+/// another game tree than `tree` or doesn't have the values yet.
+pub fn subgame_outcome_values(
+    solutions_dir: &Path,
+    subgame: &Subgame,
+    tree: &GameTree,
+) -> Result<(OutcomeValues, MatchFormat), String> {
+    let path = checkpoint_path(solutions_dir);
+    let checkpoint: MultiroundCheckpoint =
+        load(&path).map_err(|e| format!("Couldn't load checkpoint {}: {}", path.display(), e))?;
+    checkpoint
+        .check_tree(tree)
+        .map_err(|e| format!("Checkpoint {} {}", path.display(), e))?;
+    let missing = std::cell::RefCell::new(None);
+    let outcome_values =
+        checkpoint
+            .format
+            .outcome_values(subgame, |subgame| match checkpoint.evs.get(subgame) {
+                Some(ev) => *ev,
+                None => {
+                    missing.replace(Some(subgame.clone()));
+                    0.0
+                }
+            });
+    if let Some(subgame) = missing.into_inner() {
+        return Err(format!(
+            "Checkpoint {} has no value for subgame {}, has the solve finished?",
+            path.display(),
+            subgame
+        ));
+    }
+    Ok((outcome_values, checkpoint.format))
+}
+
+/// The last iteration saved in `dir`: the one its manifest names, or
+/// without a manifest the largest `N` such that `strategy_{N}.bincode`
+/// exists.
+pub fn latest_iteration(dir: &Path) -> bincode::Result<Option<usize>> {
+    if let Some(iteration) = load_manifest(dir)?.and_then(|m| m.last_iteration) {
+        return Ok(Some(iteration));
+    }
+    lazy_static! {
+        static ref RE: Regex = Regex::new(r"^strategy_([[:digit:]]+)\.bincode$").unwrap();
+    }
+    let mut max_iteration = None;
+    for entry in fs::read_dir(dir)? {
+        let filename = entry?.file_name();
+        if let Some(c) = filename.to_str().and_then(|f| RE.captures(f)) {
+            let iteration = c[1].parse::<usize>().unwrap();
+            max_iteration = max_iteration.max(Some(iteration));
+        }
+    }
+    Ok(max_iteration)
+}
+
+/// Write the CFR state, current strategy and average strategy for one
+/// iteration into `dir`.
+pub fn save_iteration(
+    dir: &Path,
+    iteration: usize,
+    cfr: &CFR,
+    strategy: &Strategy,
+) -> bincode::Result<()> {
+    save(&debug_path(dir, iteration), cfr)?;
+    save(&strategy_path(dir, iteration), strategy)?;
+    save(
+        &average_strategy_path(dir, iteration),
+        &cfr.average_strategy,
+    )
+}
+
+/// The file a strategy was taken from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Artifact {
+    /// `average_strategy_{N}.bincode`
+    AverageStrategy,
+    /// The average strategy inside `debug_{N}.bincode`.
+    Debug,
+    /// `strategy_{N}.bincode`, the strategy of the last iteration.
+    Strategy,
+}
+
+/// Whatever was saved for one iteration of a solve.
+pub struct Solution {
+    pub dir: PathBuf,
+    pub iteration: usize,
+    pub manifest: Option<Manifest>,
+    /// The full solver state, if it was saved.
+    pub cfr: Option<CFR>,
+    pub strategy: Strategy,
+    /// The standalone average strategy, if it was saved.
+    pub average_strategy: Option<Strategy>,
+}
+
+impl Solution {
+    /// The average strategy, preferring the standalone file over the one in
+    /// the CFR dump.
+    pub fn average_strategy(&self) -> Option<(&Strategy, Artifact)> {
+        if let Some(strategy) = &self.average_strategy {
+            return Some((strategy, Artifact::AverageStrategy));
+        }
+        self.cfr
+            .as_ref()
+            .map(|cfr| (&cfr.average_strategy, Artifact::Debug))
+    }
+
+    /// The strategy a bot should play.  This is the average strategy if
+    /// `average` is set and one was saved, and the current strategy otherwise.
+    pub fn bot_strategy(&self, average: bool) -> (&Strategy, Artifact) {
+        if average {
+            if let Some(result) = self.average_strategy() {
+                return result;
+            }
+        }
+        (&self.strategy, Artifact::Strategy)
+    }
+
+    pub fn artifact_path(&self, artifact: Artifact) -> PathBuf {
+        match artifact {
+            Artifact::AverageStrategy => average_strategy_path(&self.dir, self.iteration),
+            Artifact::Debug => debug_path(&self.dir, self.iteration),
+            Artifact::Strategy => strategy_path(&self.dir, self.iteration),
+        }
+    }
+
+    /// A line for the user saying which strategy was picked, and why if it is
+    /// not the one they asked for.
+    pub fn describe(&self, average: bool) -> String {
+        let (_, artifact) = self.bot_strategy(average);
+        let path = self.artifact_path(artifact);
+        match artifact {
+            Artifact::AverageStrategy => format!("Using average strategy from {}", path.display()),
+            Artifact::Debug => format!(
+                "Using average strategy from the solver state in {}",
+                path.display()
+            ),
+            Artifact::Strategy if average => format!(
+                "No average strategy saved for iteration {}, using the last iteration's strategy from {}",
+                self.iteration,
+                path.display()
+            ),
+            Artifact::Strategy => format!("Using current strategy from {}", path.display()),
+        }
+    }
+}
+
+/// Load whatever was saved for `iteration` in `dir`.  Only
+/// `strategy_{N}.bincode` has to exist.  Fails if the manifest says the
+/// solution is for another tree than `tree`, or the strategy doesn't fit it.
+pub fn load_solution(dir: &Path, iteration: usize, tree: &GameTree) -> bincode::Result<Solution> {
+    let manifest = load_manifest(dir)?;
+    if let Some(manifest) = &manifest {
+        manifest
+            .check(tree)
+            .map_err(|e| custom_error(format!("can't use {}: {}", dir.display(), e)))?;
+    }
+    let strategy: Strategy = load(&strategy_path(dir, iteration))?;
+    if strategy.probs.offsets() != tree.action_offsets {
+        return Err(custom_error(format!(
+            "can't use {}: its strategies are for a different game tree",
+            dir.display()
+        )));
+    }
+    let average_strategy = load_if_exists(&average_strategy_path(dir, iteration))?;
+    let cfr = load_if_exists(&debug_path(dir, iteration))?;
+    Ok(Solution {
+        dir: dir.to_owned(),
+        iteration,
+        manifest,
+        cfr,
+        strategy,
+        average_strategy,
+    })
+}
+
+/// Load the last iteration saved in `dir`, like [`load_solution`].
+pub fn load_latest_solution(dir: &Path, tree: &GameTree) -> bincode::Result<Solution> {
+    let iteration = latest_iteration(dir)?.ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("no strategy_N.bincode files in {}", dir.display()),
+        )
+    })?;
+    load_solution(dir, iteration, tree)
+}
+
+/// Like [`load`], but a missing file is `None` rather than an error.
+pub fn load_if_exists<T: DeserializeOwned>(path: &Path) -> bincode::Result<Option<T>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    load(path).map(Some)
+}
@@ -0,0 +1,741 @@
+//! The rules of a multi-round match: when it ends, what each round leads to
+//! and who moves first in it.
+//!
+//! A round is played at a [`Subgame`], the score from the point of view of
+//! whoever moves first in it, and each way the round can end either finishes
+//! the match or leads to the next round.  Scores are kept as small as the
+//! rules allow, e.g. only the difference matters in a fixed number of rounds,
+//! so that every match has finitely many subgames.
+
+use num_rational::BigRational;
+use num_traits::Zero;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::game::*;
+
+/// When a match is over.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scoring {
+    /// The first to this score wins.  If both players reach it in the same
+    /// round, the last round is played again.
+    FirstTo(i8),
+    /// The first to this score wins, but only with a lead of two points.
+    WinByTwo(i8),
+    /// This many rounds are played, and the match is drawn if the scores are
+    /// equal after them.
+    Rounds(i8),
+}
+
+/// The rules of a match.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatchFormat {
+    pub scoring: Scoring,
+    /// Whether the player who didn't score moves first in the next round,
+    /// when only one of the players scored.  Otherwise, and after rounds
+    /// where both or neither scored, the players take turns.
+    pub loser_moves_first: bool,
+}
+
+/// What a round leads to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Next {
+    /// The match is over, worth this much to the round's first player: 1
+    /// for a win, -1 for a loss and 0 for a draw.
+    Over(f64),
+    /// Another round at `subgame`, where `swapped` says whether the round's
+    /// second player moves first in it.
+    Round { subgame: Subgame, swapped: bool },
+}
+
+/// The ways a round can end, as whether the first and second player scored.
+pub const ROUND_OUTCOMES: [(bool, bool); 4] =
+    [(true, true), (true, false), (false, true), (false, false)];
+
+impl Default for MatchFormat {
+    fn default() -> Self {
+        MatchFormat::first_to(5)
+    }
+}
+
+impl MatchFormat {
+    /// The first to `winning_score` wins, and the players take turns moving
+    /// first, like in the SMBC comic.
+    pub fn first_to(winning_score: i8) -> MatchFormat {
+        MatchFormat {
+            scoring: Scoring::FirstTo(winning_score),
+            loser_moves_first: false,
+        }
+    }
+
+    /// The subgame of the first round.
+    pub fn first_round(&self) -> Subgame {
+        Subgame::default()
+    }
+
+    /// What the round at `subgame` leads to when the players score
+    /// `outcomes`.
+    pub fn next(&self, subgame: &Subgame, outcomes: (bool, bool)) -> Next {
+        let (p1scored, p2scored) = outcomes;
+        let mut p1score = subgame.p1score + p1scored as i8;
+        let mut p2score = subgame.p2score + p2scored as i8;
+        let mut round = 0;
+        let over = |lead: i8| Next::Over(lead.signum() as f64);
+        match self.scoring {
+            Scoring::FirstTo(winning_score) => {
+                if p1score >= winning_score && p2score >= winning_score {
+                    p1score = winning_score - 1;
+                    p2score = winning_score - 1;
+                } else if p1score >= winning_score || p2score >= winning_score {
+                    return over(p1score - p2score);
+                }
+            }
+            Scoring::WinByTwo(winning_score) => {
+                if (p1score >= winning_score || p2score >= winning_score)
+                    && (p1score - p2score).abs() >= 2
+                {
+                    return over(p1score - p2score);
+                }
+                // Past the winning score only the lead matters.
+                let excess = p1score.min(p2score) - (winning_score - 1);
+                if excess > 0 {
+                    p1score -= excess;
+                    p2score -= excess;
+                }
+            }
+            Scoring::Rounds(rounds) => {
+                round = subgame.round + 1;
+                let lead = p1score - p2score;
+                if round >= rounds || lead.abs() > rounds - round {
+                    return over(lead);
+                }
+                p1score = lead.max(0);
+                p2score = (-lead).max(0);
+            }
+        }
+        let swapped = if self.loser_moves_first && p1scored != p2scored {
+            p1scored
+        } else {
+            true
+        };
+        let subgame = if swapped {
+            Subgame {
+                p1score: p2score,
+                p2score: p1score,
+                round,
+            }
+        } else {
+            Subgame {
+                p1score,
+                p2score,
+                round,
+            }
+        };
+        Next::Round { subgame, swapped }
+    }
+
+    /// Payoffs for the round at `subgame`, where `value(s, swapped)` is the
+    /// value to this round's first player of the next round being at `s`.
+    pub fn outcome_values_with(
+        &self,
+        subgame: &Subgame,
+        value: impl Fn(&Subgame, bool) -> f64,
+    ) -> OutcomeValues {
+        let value_of = |outcomes| match self.next(subgame, outcomes) {
+            Next::Over(value) => value,
+            Next::Round { subgame, swapped } => value(&subgame, swapped),
+        };
+        OutcomeValues {
+            both_win: value_of((true, true)),
+            p1_win: value_of((true, false)),
+            p2_win: value_of((false, true)),
+            both_lose: value_of((false, false)),
+            first_move_epsilon: 0.0,
+        }
+    }
+
+    /// Payoffs for the round at `subgame`, where `ev(s)` is the first
+    /// player's value of starting a round at `s`.  When the players swap who
+    /// moves first the value is negated.
+    pub fn outcome_values(&self, subgame: &Subgame, ev: impl Fn(&Subgame) -> f64) -> OutcomeValues {
+        self.outcome_values_with(
+            subgame,
+            |subgame, swapped| {
+                if swapped {
+                    -ev(subgame)
+                } else {
+                    ev(subgame)
+                }
+            },
+        )
+    }
+
+    /// The subgames the round at `subgame` can lead to.
+    pub fn successors(&self, subgame: &Subgame) -> Vec<Subgame> {
+        let mut result = Vec::new();
+        for outcomes in ROUND_OUTCOMES {
+            if let Next::Round { subgame, .. } = self.next(subgame, outcomes) {
+                if !result.contains(&subgame) {
+                    result.push(subgame);
+                }
+            }
+        }
+        result
+    }
+
+    /// Every subgame a match can reach, in a fixed order.
+    pub fn subgames(&self) -> Vec<Subgame> {
+        let mut result = vec![self.first_round()];
+        let mut seen: HashSet<Subgame> = result.iter().cloned().collect();
+        let mut queue: VecDeque<Subgame> = result.iter().cloned().collect();
+        while let Some(subgame) = queue.pop_front() {
+            for next in self.successors(&subgame) {
+                if seen.insert(next.clone()) {
+                    result.push(next.clone());
+                    queue.push_back(next);
+                }
+            }
+        }
+        result.sort();
+        result
+    }
+
+    /// The subgames grouped so that each group only leads to itself and to
+    /// groups before it, with each group as small as possible.  The groups
+    /// are split into levels, from the last rounds to the first, such that
+    /// groups on the same level don't lead to each other.
+    ///
+    /// With [`Scoring::FirstTo`] the groups are the pairs `(a, b)` and
+    /// `(b, a)`, since a round where nobody scores swaps the players, and the
+    /// levels are the pairs with the same total score.
+    pub fn solve_order(&self) -> Vec<Vec<Vec<Subgame>>> {
+        let subgames = self.subgames();
+        let index: HashMap<&Subgame, usize> =
+            subgames.iter().enumerate().map(|(i, s)| (s, i)).collect();
+        let successors: Vec<Vec<usize>> = subgames
+            .iter()
+            .map(|s| self.successors(s).iter().map(|n| index[n]).collect())
+            .collect();
+
+        // There are few enough subgames to find everything each one leads
+        // to directly.
+        let reachable: Vec<HashSet<usize>> = (0..subgames.len())
+            .map(|start| {
+                let mut seen = HashSet::new();
+                let mut stack = successors[start].clone();
+                while let Some(i) = stack.pop() {
+                    if seen.insert(i) {
+                        stack.extend(&successors[i]);
+                    }
+                }
+                seen
+            })
+            .collect();
+        let mut group_of = vec![usize::MAX; subgames.len()];
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        for i in 0..subgames.len() {
+            if group_of[i] != usize::MAX {
+                continue;
+            }
+            let group: Vec<usize> = (0..subgames.len())
+                .filter(|&j| j == i || (reachable[i].contains(&j) && reachable[j].contains(&i)))
+                .collect();
+            for &j in &group {
+                group_of[j] = groups.len();
+            }
+            groups.push(group);
+        }
+
+        // A group's level is one more than the highest level it leads to.
+        let mut levels = vec![None; groups.len()];
+        fn level_of(
+            group: usize,
+            groups: &[Vec<usize>],
+            group_of: &[usize],
+            successors: &[Vec<usize>],
+            levels: &mut Vec<Option<usize>>,
+        ) -> usize {
+            if let Some(level) = levels[group] {
+                return level;
+            }
+            let mut level = 0;
+            for &i in &groups[group] {
+                for &j in &successors[i] {
+                    if group_of[j] != group {
+                        level = level
+                            .max(level_of(group_of[j], groups, group_of, successors, levels) + 1);
+                    }
+                }
+            }
+            levels[group] = Some(level);
+            level
+        }
+        let mut result: Vec<Vec<Vec<Subgame>>> = Vec::new();
+        for group in 0..groups.len() {
+            let level = level_of(group, &groups, &group_of, &successors, &mut levels);
+            if result.len() <= level {
+                result.resize(level + 1, Vec::new());
+            }
+            let mut members: Vec<Subgame> =
+                groups[group].iter().map(|&i| subgames[i].clone()).collect();
+            // Largest scores first, so FirstTo pairs are listed as before.
+            members.sort_by(|a, b| b.cmp(a));
+            result[level].push(members);
+        }
+        for level in result.iter_mut() {
+            level.sort_by(|a, b| b[0].cmp(&a[0]));
+        }
+        result
+    }
+
+    /// Whether a match can end in a draw.
+    pub fn can_draw(&self) -> bool {
+        matches!(self.scoring, Scoring::Rounds(_))
+    }
+
+    /// The format in words, e.g. "first to 5".
+    pub fn describe(&self) -> String {
+        let scoring = match self.scoring {
+            Scoring::FirstTo(score) => format!("first to {}", score),
+            Scoring::WinByTwo(score) => format!("first to {}, winning by two", score),
+            Scoring::Rounds(rounds) => format!("{} rounds", rounds),
+        };
+        if self.loser_moves_first {
+            return format!("{}, the loser of a round moves first", scoring);
+        }
+        scoring
+    }
+}
+
+impl std::fmt::Display for MatchFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.scoring {
+            Scoring::FirstTo(score) => write!(f, "first-to:{}", score)?,
+            Scoring::WinByTwo(score) => write!(f, "win-by-two:{}", score)?,
+            Scoring::Rounds(rounds) => write!(f, "rounds:{}", rounds)?,
+        }
+        if self.loser_moves_first {
+            write!(f, ",loser-first")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for MatchFormat {
+    type Err = String;
+
+    /// Parses `first-to:N`, `win-by-two:N` or `rounds:N`, optionally
+    /// followed by `,loser-first`.
+    fn from_str(s: &str) -> Result<MatchFormat, String> {
+        let error = || {
+            format!(
+                "invalid match format {:?}, expected first-to:N, win-by-two:N or rounds:N, \
+                 optionally followed by ,loser-first",
+                s
+            )
+        };
+        let (scoring, loser_moves_first) = match s.split_once(',') {
+            None => (s, false),
+            Some((scoring, "loser-first")) => (scoring, true),
+            Some(_) => return Err(error()),
+        };
+        let (kind, number) = scoring.split_once(':').ok_or_else(error)?;
+        let number: i8 = number.trim().parse().map_err(|_| error())?;
+        if number < 1 {
+            return Err(error());
+        }
+        let scoring = match kind {
+            "first-to" => Scoring::FirstTo(number),
+            "win-by-two" => Scoring::WinByTwo(number),
+            "rounds" => Scoring::Rounds(number),
+            _ => return Err(error()),
+        };
+        Ok(MatchFormat {
+            scoring,
+            loser_moves_first,
+        })
+    }
+}
+
+/// Solve `matrix * x = rhs` by Gaussian elimination with partial pivoting.
+/// The systems here are a few unknowns for each subgame in a group.
+pub(crate) fn solve_linear(mut matrix: Vec<Vec<f64>>, mut rhs: Vec<f64>) -> Vec<f64> {
+    let n = rhs.len();
+    for column in 0..n {
+        let pivot = (column..n)
+            .max_by(|&a, &b| matrix[a][column].abs().total_cmp(&matrix[b][column].abs()))
+            .unwrap();
+        matrix.swap(column, pivot);
+        rhs.swap(column, pivot);
+        for row in column + 1..n {
+            let factor = matrix[row][column] / matrix[column][column];
+            if factor == 0.0 {
+                continue;
+            }
+            let pivot_row = matrix[column].clone();
+            for (entry, pivot) in matrix[row].iter_mut().zip(&pivot_row).skip(column) {
+                *entry -= factor * pivot;
+            }
+            rhs[row] -= factor * rhs[column];
+        }
+    }
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum: f64 = (row + 1..n).map(|k| matrix[row][k] * x[k]).sum();
+        x[row] = (rhs[row] - sum) / matrix[row][row];
+    }
+    x
+}
+
+/// Solve `matrix * x = rhs` exactly by Gaussian elimination, or `None` if the
+/// matrix is singular.
+pub(crate) fn solve_linear_exact(
+    mut matrix: Vec<Vec<BigRational>>,
+    mut rhs: Vec<BigRational>,
+) -> Option<Vec<BigRational>> {
+    let n = rhs.len();
+    for column in 0..n {
+        let pivot = (column..n).find(|&row| !matrix[row][column].is_zero())?;
+        matrix.swap(column, pivot);
+        rhs.swap(column, pivot);
+        for row in column + 1..n {
+            if matrix[row][column].is_zero() {
+                continue;
+            }
+            let factor = &matrix[row][column] / &matrix[column][column];
+            let pivot_row = matrix[column].clone();
+            for (entry, pivot) in matrix[row].iter_mut().zip(&pivot_row).skip(column) {
+                *entry -= &factor * pivot;
+            }
+            let pivot_rhs = rhs[column].clone();
+            rhs[row] -= factor * pivot_rhs;
+        }
+    }
+    let mut x = vec![BigRational::zero(); n];
+    for row in (0..n).rev() {
+        let sum: BigRational = (row + 1..n).map(|k| &matrix[row][k] * &x[k]).sum();
+        x[row] = (&rhs[row] - sum) / &matrix[row][row];
+    }
+    Some(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subgame(s: &str) -> Subgame {
+        s.parse().unwrap()
+    }
+
+    fn format(s: &str) -> MatchFormat {
+        s.parse().unwrap()
+    }
+
+    fn round(s: &str, swapped: bool) -> Next {
+        Next::Round {
+            subgame: subgame(s),
+            swapped,
+        }
+    }
+
+    fn order(format: &MatchFormat) -> Vec<Vec<Vec<String>>> {
+        format
+            .solve_order()
+            .iter()
+            .map(|level| {
+                level
+                    .iter()
+                    .map(|group| group.iter().map(|s| s.to_string()).collect())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parsing() {
+        assert_eq!(format("first-to:5"), MatchFormat::default());
+        assert_eq!(
+            format("rounds:3,loser-first"),
+            MatchFormat {
+                scoring: Scoring::Rounds(3),
+                loser_moves_first: true,
+            }
+        );
+        for s in ["first-to:3", "win-by-two:2,loser-first", "rounds:4"] {
+            assert_eq!(format(s).to_string(), s);
+        }
+        for invalid in [
+            "first-to",
+            "first-to:0",
+            "first-to:x",
+            "best-of:3",
+            "rounds:3,winner-first",
+        ] {
+            assert!(invalid.parse::<MatchFormat>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn next_first_to() {
+        let format = format("first-to:2");
+        // Scores are from the point of view of whoever moves first, and the
+        // players take turns.
+        assert_eq!(
+            format.next(&subgame("0,0"), (true, false)),
+            round("0,1", true)
+        );
+        assert_eq!(
+            format.next(&subgame("0,0"), (false, true)),
+            round("1,0", true)
+        );
+        assert_eq!(
+            format.next(&subgame("0,0"), (false, false)),
+            round("0,0", true)
+        );
+        assert_eq!(format.next(&subgame("1,0"), (true, false)), Next::Over(1.0));
+        assert_eq!(format.next(&subgame("1,0"), (true, true)), Next::Over(1.0));
+        assert_eq!(
+            format.next(&subgame("0,1"), (false, true)),
+            Next::Over(-1.0)
+        );
+        // Both reaching the winning score plays the last round again.
+        assert_eq!(
+            format.next(&subgame("1,1"), (true, true)),
+            round("1,1", true)
+        );
+    }
+
+    #[test]
+    fn next_win_by_two() {
+        let format = format("win-by-two:2");
+        assert_eq!(format.next(&subgame("1,0"), (true, false)), Next::Over(1.0));
+        assert_eq!(
+            format.next(&subgame("1,1"), (true, false)),
+            round("1,2", true)
+        );
+        assert_eq!(format.next(&subgame("2,1"), (true, false)), Next::Over(1.0));
+        assert_eq!(
+            format.next(&subgame("1,2"), (false, true)),
+            Next::Over(-1.0)
+        );
+        // Past the winning score only the lead is kept.
+        assert_eq!(
+            format.next(&subgame("1,2"), (true, false)),
+            round("1,1", true)
+        );
+        assert_eq!(
+            format.next(&subgame("1,2"), (true, true)),
+            round("2,1", true)
+        );
+    }
+
+    #[test]
+    fn next_rounds() {
+        let format = format("rounds:3");
+        assert_eq!(
+            format.next(&subgame("0,0,0"), (true, false)),
+            round("0,1,1", true)
+        );
+        // Only the lead is kept.
+        assert_eq!(
+            format.next(&subgame("1,0,1"), (true, true)),
+            round("0,1,2", true)
+        );
+        // A lead of two can't be caught up in the last round.
+        assert_eq!(
+            format.next(&subgame("1,0,1"), (true, false)),
+            Next::Over(1.0)
+        );
+        assert_eq!(
+            format.next(&subgame("0,1,2"), (false, false)),
+            Next::Over(-1.0)
+        );
+        assert_eq!(
+            format.next(&subgame("0,0,2"), (true, true)),
+            Next::Over(0.0)
+        );
+        assert!(format.can_draw());
+        assert!(!MatchFormat::default().can_draw());
+    }
+
+    #[test]
+    fn next_loser_first() {
+        let format = format("first-to:2,loser-first");
+        // The player who didn't score moves first, so the scores are only
+        // swapped when the first player scored.
+        assert_eq!(
+            format.next(&subgame("0,0"), (true, false)),
+            round("0,1", true)
+        );
+        assert_eq!(
+            format.next(&subgame("0,0"), (false, true)),
+            round("0,1", false)
+        );
+        // Otherwise the players take turns.
+        assert_eq!(
+            format.next(&subgame("0,0"), (false, false)),
+            round("0,0", true)
+        );
+        assert_eq!(
+            format.next(&subgame("1,1"), (true, true)),
+            round("1,1", true)
+        );
+    }
+
+    #[test]
+    fn outcome_values() {
+        let format = format("first-to:2");
+        let ev = |subgame: &Subgame| match subgame.to_string().as_str() {
+            "(0, 1)" => -0.25,
+            "(1, 1)" => 0.0,
+            _ => unreachable!(),
+        };
+        let values = format.outcome_values(&subgame("1,0"), ev);
+        assert_eq!(values.both_win, 1.0);
+        assert_eq!(values.p1_win, 1.0);
+        assert_eq!(values.p2_win, 0.0);
+        // The next round's first player is behind, which is good for this
+        // round's first player.
+        assert_eq!(values.both_lose, 0.25);
+    }
+
+    #[test]
+    fn solve_order_first_to() {
+        assert_eq!(
+            order(&format("first-to:2")),
+            vec![
+                vec![vec!["(1, 1)"]],
+                vec![vec!["(1, 0)", "(0, 1)"]],
+                vec![vec!["(0, 0)"]],
+            ]
+        );
+        let order = format("first-to:3").solve_order();
+        assert_eq!(order.len(), 5);
+        for (level, total) in order.iter().zip((0..5).rev()) {
+            for group in level {
+                assert!(group.len() <= 2);
+                for subgame in group {
+                    assert_eq!(subgame.p1score + subgame.p2score, total);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn solve_order_win_by_two() {
+        assert_eq!(
+            order(&format("win-by-two:2")),
+            vec![
+                vec![vec!["(2, 1)", "(1, 2)", "(1, 1)"]],
+                vec![vec!["(1, 0)", "(0, 1)"]],
+                vec![vec!["(0, 0)"]],
+            ]
+        );
+    }
+
+    #[test]
+    fn solve_order_rounds() {
+        assert_eq!(
+            order(&format("rounds:3")),
+            vec![
+                vec![
+                    vec!["(1, 0) in round 3"],
+                    vec!["(0, 1) in round 3"],
+                    vec!["(0, 0) in round 3"],
+                ],
+                vec![
+                    vec!["(1, 0) in round 2"],
+                    vec!["(0, 1) in round 2"],
+                    vec!["(0, 0) in round 2"],
+                ],
+                vec![vec!["(0, 0)"]],
+            ]
+        );
+    }
+
+    #[test]
+    fn solve_order_loser_first() {
+        // Nobody scoring still swaps the players, so the pairs stay
+        // together.
+        assert_eq!(
+            order(&format("first-to:2,loser-first")),
+            vec![
+                vec![vec!["(1, 1)"]],
+                vec![vec!["(1, 0)", "(0, 1)"]],
+                vec![vec!["(0, 0)"]],
+            ]
+        );
+    }
+
+    #[test]
+    fn solve_order_covers_every_subgame_once() {
+        for s in [
+            "first-to:5",
+            "first-to:4,loser-first",
+            "win-by-two:3",
+            "win-by-two:3,loser-first",
+            "rounds:5",
+            "rounds:4,loser-first",
+        ] {
+            let format = format(s);
+            let order = format.solve_order();
+            let mut solved: Vec<Subgame> = order.iter().flatten().flatten().cloned().collect();
+            solved.sort();
+            assert_eq!(solved, format.subgames(), "{}", s);
+            // Each group only leads to itself and to earlier levels.
+            let mut earlier: HashSet<Subgame> = HashSet::new();
+            for level in &order {
+                for group in level {
+                    for subgame in group {
+                        for next in format.successors(subgame) {
+                            assert!(
+                                group.contains(&next) || earlier.contains(&next),
+                                "{}: {} leads to {}",
+                                s,
+                                subgame,
+                                next
+                            );
+                        }
+                    }
+                }
+                earlier.extend(level.iter().flatten().cloned());
+            }
+        }
+    }
+
+    #[test]
+    fn linear_systems() {
+        // 2x + y - z = 8, -3x - y + 2z = -11 and -2x + y + 2z = -3 have
+        // the solution x = 2, y = 3, z = -1.
+        let matrix = vec![
+            vec![2.0, 1.0, -1.0],
+            vec![-3.0, -1.0, 2.0],
+            vec![-2.0, 1.0, 2.0],
+        ];
+        let rhs = vec![8.0, -11.0, -3.0];
+        let x = solve_linear(matrix.clone(), rhs.clone());
+        for (x, expected) in x.iter().zip([2.0, 3.0, -1.0]) {
+            assert!((x - expected).abs() < 1e-12, "{} != {}", x, expected);
+        }
+
+        let exact = |n: f64| BigRational::from_float(n).unwrap();
+        let x = solve_linear_exact(
+            matrix
+                .iter()
+                .map(|row| row.iter().map(|&a| exact(a)).collect())
+                .collect(),
+            rhs.iter().map(|&b| exact(b)).collect(),
+        );
+        assert_eq!(x, Some(vec![exact(2.0), exact(3.0), exact(-1.0)]));
+
+        // A zero on the diagonal needs the rows swapped.
+        let x = solve_linear(vec![vec![0.0, 1.0], vec![1.0, 1.0]], vec![2.0, 3.0]);
+        assert_eq!(x, vec![1.0, 2.0]);
+
+        let singular = vec![vec![exact(1.0), exact(2.0)], vec![exact(2.0), exact(4.0)]];
+        assert_eq!(
+            solve_linear_exact(singular, vec![exact(1.0), exact(2.0)]),
+            None
+        );
+    }
+}
@@ -0,0 +1,247 @@
+//! Monte Carlo CFR, which samples parts of the tree on each iteration instead
+//! of traversing all of it.
+//!
+//! Both variants here follow [Lanctot et al.,
+//! "Monte Carlo Sampling for Regret Minimization in Extensive
+//! Games"](https://papers.nips.cc/paper/3713-monte-carlo-sampling-for-regret-minimization-in-extensive-games).
+//! Goals are dealt from the tree's goal distribution on every traversal, and
+//! the randomness comes from an RNG owned by the caller so runs can be
+//! seeded.
+
+use rand::distributions::WeightedIndex;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::game::*;
+use crate::solver::InfoStateRegrets;
+use crate::strategy::Strategy;
+use crate::table::*;
+
+/// Which parts of the tree a traversal samples.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub enum Sampling {
+    /// Sample chance and the opponent's moves, and try every move of the
+    /// player being updated.
+    External,
+    /// Sample a single terminal history.  The updated player's moves are
+    /// sampled from their strategy mixed with `exploration` of the uniform
+    /// strategy.
+    Outcome { exploration: f64 },
+}
+
+/// State of an MCCFR solve of a single round.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MCCFR {
+    pub sampling: Sampling,
+    pub total_regrets: InfoStateRegrets,
+    /// Unnormalized sum of the strategies played, weighted by how often each
+    /// information state was reached.
+    pub strategy_sums: ActionTable,
+    /// Number of traversals run for each player.
+    pub t: usize,
+}
+
+impl MCCFR {
+    pub fn new(tree: &GameTree, sampling: Sampling) -> MCCFR {
+        MCCFR {
+            sampling,
+            total_regrets: InfoStateRegrets(ActionTable::new(tree, 0.0)),
+            strategy_sums: ActionTable::new(tree, 0.0),
+            t: 0,
+        }
+    }
+
+    /// Run `traversals` sampled traversals for each player, alternating
+    /// between them.
+    pub fn run<R: Rng>(
+        &mut self,
+        tree: &GameTree,
+        outcome_values: &OutcomeValues,
+        traversals: usize,
+        rng: &mut R,
+    ) {
+        for _ in 0..traversals {
+            for traverser in [Player::Player1, Player::Player2] {
+                let (p1goal, p2goal) = tree.goals.sample(rng);
+                let root = MetaState {
+                    state: 0,
+                    p1goal,
+                    p2goal,
+                };
+                match self.sampling {
+                    Sampling::External => {
+                        self.external_sampling(tree, outcome_values, &root, traverser, rng);
+                    }
+                    Sampling::Outcome { exploration } => {
+                        self.outcome_sampling(
+                            tree,
+                            outcome_values,
+                            &root,
+                            traverser,
+                            exploration,
+                            1.0,
+                            1.0,
+                            rng,
+                        );
+                    }
+                }
+            }
+            self.t += 1;
+        }
+    }
+
+    /// The regret matching strategy, which is what the next traversal plays.
+    pub fn current_strategy(&self, tree: &GameTree) -> Strategy {
+        self.total_regrets.regret_matching_strategy(tree)
+    }
+
+    /// The average strategy, which is what converges to an equilibrium.
+    /// Information states that were never reached are uniform.
+    pub fn average_strategy(&self) -> Strategy {
+        let mut probs = self.strategy_sums.clone();
+        for (_, probs) in probs.iter_mut() {
+            let total: f64 = probs.iter().sum();
+            if total > 0.0 {
+                probs.iter_mut().for_each(|p| *p /= total);
+            } else {
+                let nchildren = probs.len();
+                probs.fill(1.0 / nchildren as f64);
+            }
+        }
+        Strategy { probs }
+    }
+
+    fn regret_matching(&self, infostate: &InfoState) -> Vec<f64> {
+        let regrets = &self.total_regrets.0[infostate];
+        let positive_regret: f64 = regrets.iter().map(|r| f64::max(*r, 0.0)).sum();
+        if positive_regret > 0.0 {
+            regrets
+                .iter()
+                .map(|r| f64::max(*r, 0.0) / positive_regret)
+                .collect()
+        } else {
+            vec![1.0 / regrets.len() as f64; regrets.len()]
+        }
+    }
+
+    /// The value of `metastate` to `traverser` under the current strategy,
+    /// estimated by sampling every move except the traverser's.
+    fn external_sampling<R: Rng>(
+        &mut self,
+        tree: &GameTree,
+        outcome_values: &OutcomeValues,
+        metastate: &MetaState,
+        traverser: Player,
+        rng: &mut R,
+    ) -> f64 {
+        if let Some(outcomes) = metastate.outcomes(tree) {
+            let value = outcome_values.evaluate(metastate, tree, outcomes);
+            return if traverser == Player::Player1 {
+                value
+            } else {
+                -value
+            };
+        }
+        let infostate = metastate.info_state(tree);
+        let strategy = self.regret_matching(&infostate);
+        let children = metastate.children(tree);
+        if tree.current_player[metastate.state] == traverser {
+            let child_values: Vec<f64> = children
+                .iter()
+                .map(|child| self.external_sampling(tree, outcome_values, child, traverser, rng))
+                .collect();
+            let value: f64 = itertools::zip(&strategy, &child_values)
+                .map(|(p, v)| p * v)
+                .sum();
+            for (regret, child_value) in self.total_regrets.0[&infostate]
+                .iter_mut()
+                .zip(&child_values)
+            {
+                *regret += child_value - value;
+            }
+            return value;
+        }
+        // The opponent plays on-policy, so every visit counts the same
+        // towards their average strategy.
+        for (sum, p) in self.strategy_sums[&infostate].iter_mut().zip(&strategy) {
+            *sum += p;
+        }
+        let action = WeightedIndex::new(&strategy).unwrap().sample(rng);
+        self.external_sampling(tree, outcome_values, &children[action], traverser, rng)
+    }
+
+    /// An importance-weighted estimate of the value of `metastate` to
+    /// `traverser`, from a single sampled history.  `opponent_reach` is the
+    /// probability of the opponent's moves so far and `sample_reach` the
+    /// probability of having sampled them.
+    #[allow(clippy::too_many_arguments)]
+    fn outcome_sampling<R: Rng>(
+        &mut self,
+        tree: &GameTree,
+        outcome_values: &OutcomeValues,
+        metastate: &MetaState,
+        traverser: Player,
+        exploration: f64,
+        opponent_reach: f64,
+        sample_reach: f64,
+        rng: &mut R,
+    ) -> f64 {
+        if let Some(outcomes) = metastate.outcomes(tree) {
+            let value = outcome_values.evaluate(metastate, tree, outcomes);
+            return if traverser == Player::Player1 {
+                value
+            } else {
+                -value
+            };
+        }
+        let infostate = metastate.info_state(tree);
+        let strategy = self.regret_matching(&infostate);
+        let children = metastate.children(tree);
+        let is_traverser = tree.current_player[metastate.state] == traverser;
+        let sample_probs: Vec<f64> = if is_traverser {
+            let uniform = 1.0 / strategy.len() as f64;
+            strategy
+                .iter()
+                .map(|p| exploration * uniform + (1.0 - exploration) * p)
+                .collect()
+        } else {
+            strategy.clone()
+        };
+        let action = WeightedIndex::new(&sample_probs).unwrap().sample(rng);
+        let child_value = self.outcome_sampling(
+            tree,
+            outcome_values,
+            &children[action],
+            traverser,
+            exploration,
+            if is_traverser {
+                opponent_reach
+            } else {
+                opponent_reach * strategy[action]
+            },
+            sample_reach * sample_probs[action],
+            rng,
+        );
+        // Moves that weren't sampled are estimated to be worth 0.
+        let mut child_values = vec![0.0; children.len()];
+        child_values[action] = child_value / sample_probs[action];
+        let value: f64 = itertools::zip(&strategy, &child_values)
+            .map(|(p, v)| p * v)
+            .sum();
+
+        let weight = opponent_reach / sample_reach;
+        if is_traverser {
+            for (regret, child_value) in self.total_regrets.0[&infostate]
+                .iter_mut()
+                .zip(&child_values)
+            {
+                *regret += (child_value - value) * weight;
+            }
+        } else {
+            for (sum, p) in self.strategy_sums[&infostate].iter_mut().zip(&strategy) {
+                *sum += p * weight;
+            }
+        }
+        value
+    }
+}
@@ -0,0 +1,458 @@
+//! Playing multi-round matches: a human against the bot, shared by the
+//! `tictac play` prompt and the `tictac serve` HTTP server, and bots against
+//! each other for `tictac simulate` and `tictac head-to-head`.
+//!
+//! Each round is played with the solution of the subgame at the current score
+//! from a `tictac solve-match` output directory, or with a single round's
+//! solution in every round.  The [`MatchFormat`] says when the match is over
+//! and who moves first in the next round.
+
+use num_rational::BigRational;
+use num_traits::{One, ToPrimitive, Zero};
+use rand::distributions::WeightedIndex;
+use rand::Rng;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::game::*;
+use crate::io;
+use crate::match_format::*;
+use crate::strategy::Strategy;
+
+/// One side of a match.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Human,
+    Bot,
+}
+
+/// How a round ended, from the human's point of view.
+#[derive(Serialize, Debug, Clone)]
+pub struct RoundResult {
+    /// The human's result for the round.
+    pub outcome: Outcome,
+    pub human_goal: Outcome,
+    pub bot_goal: Outcome,
+    pub human_scored: bool,
+    pub bot_scored: bool,
+    /// The final board as the human saw it: the move number on each square
+    /// in row-major order, or 0 if it is empty.
+    pub board: Vec<u8>,
+}
+
+pub struct Match {
+    /// Where to load each subgame's solution from, or `None` to play every
+    /// round with `solution`.
+    solutions_dir: Option<PathBuf>,
+    pub format: MatchFormat,
+    average_strategy: bool,
+    /// The solution for the current round's subgame.
+    pub solution: io::Solution,
+    pub metastate: MetaState,
+    pub displayed: DisplayedState,
+    pub human_player: Player,
+    pub human_score: i8,
+    pub bot_score: i8,
+    /// The current round's subgame, which may differ from the scores when
+    /// the format only keeps track of the lead.
+    subgame: Subgame,
+    /// The human's value of the match once it is over.
+    result: Option<f64>,
+}
+
+impl Match {
+    /// Start a match, dealing goals and picking who moves first at random.
+    /// `solutions_dir` is the output of `tictac solve-match`, and
+    /// `average_strategy` picks the bot's strategy like
+    /// [`io::Solution::bot_strategy`].
+    pub fn new<R: Rng + ?Sized>(
+        tree: &GameTree,
+        solutions_dir: &Path,
+        format: MatchFormat,
+        average_strategy: bool,
+        rng: &mut R,
+    ) -> bincode::Result<Match> {
+        let solution = load_subgame(tree, solutions_dir, &format.first_round(), &format)?;
+        let mut result = Match::single_round(tree, solution, format, average_strategy, rng);
+        result.solutions_dir = Some(solutions_dir.to_owned());
+        Ok(result)
+    }
+
+    /// Start a match where the bot plays every round with `solution`, the
+    /// solution of a single round.
+    pub fn single_round<R: Rng + ?Sized>(
+        tree: &GameTree,
+        solution: io::Solution,
+        format: MatchFormat,
+        average_strategy: bool,
+        rng: &mut R,
+    ) -> Match {
+        let (p1goal, p2goal) = tree.goals.sample(rng);
+        let human_player = if rng.gen_bool(0.5) {
+            Player::Player1
+        } else {
+            Player::Player2
+        };
+        Match {
+            solutions_dir: None,
+            format,
+            average_strategy,
+            solution,
+            metastate: MetaState {
+                state: 0,
+                p1goal,
+                p2goal,
+            },
+            displayed: DisplayedState::start(tree),
+            human_player,
+            human_score: 0,
+            bot_score: 0,
+            subgame: format.first_round(),
+            result: None,
+        }
+    }
+
+    /// Whether each round is played with the solution of its subgame.
+    pub fn is_multiround(&self) -> bool {
+        self.solutions_dir.is_some()
+    }
+
+    /// The subgame of this round, with the first player's score first.
+    pub fn subgame(&self) -> Subgame {
+        self.subgame.clone()
+    }
+
+    pub fn bot_strategy(&self) -> &Strategy {
+        self.solution.bot_strategy(self.average_strategy).0
+    }
+
+    pub fn describe_strategy(&self) -> String {
+        self.solution.describe(self.average_strategy)
+    }
+
+    pub fn human_goal(&self) -> Outcome {
+        self.goal(self.human_player)
+    }
+
+    pub fn bot_goal(&self) -> Outcome {
+        self.goal(self.human_player.opponent())
+    }
+
+    fn goal(&self, player: Player) -> Outcome {
+        match player {
+            Player::Player1 => self.metastate.p1goal,
+            Player::Player2 => self.metastate.p2goal,
+        }
+    }
+
+    pub fn to_move(&self, tree: &GameTree) -> Side {
+        if tree.current_player[self.metastate.state] == self.human_player {
+            Side::Human
+        } else {
+            Side::Bot
+        }
+    }
+
+    /// Whether the match is over.
+    pub fn is_over(&self) -> bool {
+        self.result.is_some()
+    }
+
+    /// Whoever won the match, if it is over and wasn't drawn.
+    pub fn winner(&self) -> Option<Side> {
+        match self.result {
+            Some(result) if result > 0.0 => Some(Side::Human),
+            Some(result) if result < 0.0 => Some(Side::Bot),
+            _ => None,
+        }
+    }
+
+    /// Play the human's move on `square` of the displayed board, counting
+    /// from 0.  Returns false, changing nothing, if the move isn't legal.
+    pub fn play_human(&mut self, tree: &GameTree, square: usize) -> bool {
+        match self.displayed.play_square(tree, square) {
+            Some(state) => {
+                self.metastate.state = state;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Let the bot move, and return the square it played on the displayed
+    /// board.
+    pub fn play_bot<R: Rng + ?Sized>(&mut self, tree: &GameTree, rng: &mut R) -> usize {
+        let probs = &self.bot_strategy().probs[&self.metastate.info_state(tree)];
+        let choice = rng.sample(WeightedIndex::new(probs).unwrap());
+        self.metastate.state = self.displayed.play_child(tree, choice);
+        self.displayed.view.last_move().unwrap()
+    }
+
+    /// Score the round if it has ended, and unless that ends the match, deal
+    /// the next one.
+    pub fn finish_round<R: Rng + ?Sized>(
+        &mut self,
+        tree: &GameTree,
+        rng: &mut R,
+    ) -> bincode::Result<Option<RoundResult>> {
+        let outcome = match tree.terminals[self.metastate.state] {
+            Some(outcome) => outcome,
+            None => return Ok(None),
+        };
+        let human_outcome = match self.human_player {
+            Player::Player1 => outcome,
+            Player::Player2 => outcome.reverse(),
+        };
+        let result = RoundResult {
+            outcome: human_outcome,
+            human_goal: self.human_goal(),
+            bot_goal: self.bot_goal(),
+            human_scored: human_outcome == self.human_goal(),
+            bot_scored: human_outcome.reverse() == self.bot_goal(),
+            board: self.displayed.view.cells().to_vec(),
+        };
+        self.human_score += result.human_scored as i8;
+        self.bot_score += result.bot_scored as i8;
+        let outcomes = match self.human_player {
+            Player::Player1 => (result.human_scored, result.bot_scored),
+            Player::Player2 => (result.bot_scored, result.human_scored),
+        };
+        match self.format.next(&self.subgame, outcomes) {
+            Next::Over(value) => {
+                self.result = Some(match self.human_player {
+                    Player::Player1 => value,
+                    Player::Player2 => -value,
+                });
+            }
+            Next::Round { subgame, swapped } => {
+                let (p1goal, p2goal) = tree.goals.sample(rng);
+                self.metastate = MetaState {
+                    state: 0,
+                    p1goal,
+                    p2goal,
+                };
+                self.displayed = DisplayedState::start(tree);
+                if swapped {
+                    self.human_player = self.human_player.opponent();
+                }
+                self.subgame = subgame;
+                if let Some(solutions_dir) = &self.solutions_dir {
+                    self.solution = load_subgame(tree, solutions_dir, &self.subgame, &self.format)?;
+                }
+            }
+        }
+        Ok(Some(result))
+    }
+}
+
+/// Load the solution of `subgame`, failing if its manifest says it is part of
+/// a match in another format.
+fn load_subgame(
+    tree: &GameTree,
+    solutions_dir: &Path,
+    subgame: &Subgame,
+    format: &MatchFormat,
+) -> bincode::Result<io::Solution> {
+    let solution = io::load_latest_solution(&io::subgame_dir(solutions_dir, subgame), tree)?;
+    let solved_for = solution
+        .manifest
+        .as_ref()
+        .and_then(|m| m.match_format.clone());
+    if let Some(solved_for) = solved_for.filter(|solved_for| *solved_for != format.to_string()) {
+        return Err(Box::new(bincode::ErrorKind::Custom(format!(
+            "{} was solved for --format {}, not {}",
+            solutions_dir.display(),
+            solved_for,
+            format
+        ))));
+    }
+    Ok(solution)
+}
+
+/// The strategy a bot plays in every subgame of a match.
+pub struct MatchStrategies {
+    pub format: MatchFormat,
+    pub strategies: HashMap<Subgame, Strategy>,
+}
+
+impl MatchStrategies {
+    /// Load every subgame of a match in `format` from the output directory
+    /// of `tictac solve-match`, picking strategies like
+    /// [`io::Solution::bot_strategy`].
+    pub fn load(
+        tree: &GameTree,
+        solutions_dir: &Path,
+        format: MatchFormat,
+        average_strategy: bool,
+    ) -> bincode::Result<MatchStrategies> {
+        let mut strategies = HashMap::new();
+        for subgame in format.subgames() {
+            let solution = load_subgame(tree, solutions_dir, &subgame, &format)?;
+            log::debug!("{}", solution.describe(average_strategy));
+            let (strategy, _) = solution.bot_strategy(average_strategy);
+            strategies.insert(subgame, strategy.clone());
+        }
+        Ok(MatchStrategies { format, strategies })
+    }
+
+    /// Play uniformly at random in every subgame.
+    pub fn uniform(tree: &GameTree, format: MatchFormat) -> MatchStrategies {
+        let uniform = Strategy::uniform(tree);
+        let strategies = format
+            .subgames()
+            .into_iter()
+            .map(|subgame| (subgame, uniform.clone()))
+            .collect();
+        MatchStrategies { format, strategies }
+    }
+
+    pub fn get(&self, subgame: &Subgame) -> &Strategy {
+        &self.strategies[subgame]
+    }
+}
+
+/// Deal goals and play a round with `p1_strategy` moving first, returning
+/// the goals and whether the first and second player scored.
+pub fn sample_round<R: Rng + ?Sized>(
+    tree: &GameTree,
+    p1_strategy: &Strategy,
+    p2_strategy: &Strategy,
+    rng: &mut R,
+) -> (MetaState, (bool, bool)) {
+    let (p1goal, p2goal) = tree.goals.sample(rng);
+    let mut metastate = MetaState {
+        state: 0,
+        p1goal,
+        p2goal,
+    };
+    loop {
+        if let Some(outcomes) = metastate.outcomes(tree) {
+            return (metastate, outcomes);
+        }
+        let strategy = match tree.current_player[metastate.state] {
+            Player::Player1 => p1_strategy,
+            Player::Player2 => p2_strategy,
+        };
+        let probs = &strategy.probs[&metastate.info_state(tree)];
+        let choice = rng.sample(WeightedIndex::new(probs).unwrap());
+        metastate.state = tree.children[metastate.state][choice];
+    }
+}
+
+/// The exact probability that each bot wins a match from every subgame,
+/// keyed by the subgame and which bot moves first in it, as
+/// `[bots[0] wins, bots[1] wins]`.  Both bots must be for the same format,
+/// and in formats with draws the two need not add up to 1.
+///
+/// Each round's outcome probabilities come from
+/// [`Strategy::outcome_probs`], and the subgames form a Markov chain which is
+/// solved one group of [`MatchFormat::solve_order`] at a time, from the last
+/// rounds to the first.  The chain is solved in exact arithmetic, with each
+/// round's outcome probabilities scaled to add up to exactly 1, so a bot
+/// playing itself wins exactly as often moving first as the other moving
+/// second.  Fails if the bots can keep playing a group of subgames forever
+/// without the match ending, e.g. by always tying in a win-by-two match.
+pub fn head_to_head(
+    tree: &GameTree,
+    bots: [&MatchStrategies; 2],
+) -> Result<HashMap<(Subgame, usize), [f64; 2]>, String> {
+    let format = &bots[0].format;
+    let mut result: HashMap<(Subgame, usize), [BigRational; 2]> = HashMap::new();
+    for group in format.solve_order().into_iter().flatten() {
+        // Unknown 2 * i + first is the probability for subgame i with bot
+        // `first` moving first.
+        let index = |subgame: &Subgame, first: usize| {
+            group
+                .iter()
+                .position(|s| s == subgame)
+                .map(|i| 2 * i + first)
+        };
+        let n = 2 * group.len();
+        let mut matrix = vec![vec![BigRational::zero(); n]; n];
+        let mut rhs = [vec![BigRational::zero(); n], vec![BigRational::zero(); n]];
+        for (i, subgame) in group.iter().enumerate() {
+            for first in 0..2 {
+                let second = 1 - first;
+                let row = 2 * i + first;
+                matrix[row][row] += BigRational::one();
+                let round =
+                    Strategy::splice(bots[first].get(subgame), bots[second].get(subgame), tree);
+                let outcome_probs = round
+                    .outcome_probs(tree)
+                    .into_iter()
+                    .map(|(outcomes, prob)| (outcomes, BigRational::from_float(prob).unwrap()))
+                    .collect::<Vec<_>>();
+                let total: BigRational = outcome_probs.iter().map(|(_, prob)| prob).sum();
+                for (outcomes, prob) in outcome_probs {
+                    let prob = prob / &total;
+                    match format.next(subgame, outcomes) {
+                        Next::Over(value) if value > 0.0 => rhs[first][row] += prob,
+                        Next::Over(value) if value < 0.0 => rhs[second][row] += prob,
+                        Next::Over(_) => {}
+                        Next::Round { subgame, swapped } => {
+                            let next_first = if swapped { second } else { first };
+                            match index(&subgame, next_first) {
+                                Some(column) => matrix[row][column] -= prob,
+                                None => {
+                                    let wins = &result[&(subgame, next_first)];
+                                    rhs[0][row] += &prob * &wins[0];
+                                    rhs[1][row] += &prob * &wins[1];
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        let [wins0, wins1] = rhs.map(|rhs| solve_linear_exact(matrix.clone(), rhs));
+        let (wins0, wins1) = wins0.zip(wins1).ok_or_else(|| {
+            let subgames: Vec<String> = group.iter().map(|s| s.to_string()).collect();
+            format!(
+                "the bots can keep playing {} forever without the match ending",
+                subgames.join(" and ")
+            )
+        })?;
+        for (i, subgame) in group.iter().enumerate() {
+            for first in 0..2 {
+                let wins = [&wins0, &wins1].map(|wins| wins[2 * i + first].clone());
+                result.insert((subgame.clone(), first), wins);
+            }
+        }
+    }
+    Ok(result
+        .into_iter()
+        .map(|(key, wins)| (key, wins.map(|wins| wins.to_f64().unwrap())))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn head_to_head_against_itself() {
+        let tree = GameTree::with_board("2x2:2".parse().unwrap());
+        for format in ["first-to:2", "win-by-two:2,loser-first", "rounds:3"] {
+            let format: MatchFormat = format.parse().unwrap();
+            let bot = MatchStrategies::uniform(&tree, format);
+            let win_probs = head_to_head(&tree, [&bot, &bot]).unwrap();
+            let first_round = format.first_round();
+            let [a_first, b_first] = [0, 1].map(|first| win_probs[&(first_round.clone(), first)]);
+            // Whichever bot moves first, it is the same one.
+            assert_eq!(a_first[0], b_first[1], "{}", format);
+            assert_eq!(a_first[1], b_first[0], "{}", format);
+        }
+    }
+
+    #[test]
+    fn head_to_head_without_an_end() {
+        // Every round on a 1x2 board is a tie, and with both players always
+        // wanting one both always score, so nobody ever gets ahead.
+        let tree = GameTree::with_board("1x2:2".parse().unwrap())
+            .with_goals("joint:0,0,0,0,0,0,0,0,1".parse().unwrap());
+        let format: MatchFormat = "win-by-two:2".parse().unwrap();
+        let bot = MatchStrategies::uniform(&tree, format);
+        assert!(head_to_head(&tree, [&bot, &bot]).is_err());
+    }
+}
@@ -0,0 +1,701 @@
+//! JSON and CSV forms of solver artifacts, for reading results in other
+//! tools and for keeping them readable if the bincode layout changes.
+//!
+//! States are written as the squares played so far in order, counting from
+//! 1 in row-major order and separated by spaces, so `"5 1"` is the state
+//! after moves in the centre and then the top left corner, and `""` is the
+//! empty board.  The board is in the orientation the game tree keeps, and
+//! each action is written as the square it plays on.  These keys only depend
+//! on the board, not on internal [`StateId`]s.  Values that aren't defined,
+//! like a best response's values at states the opponent never reaches, are
+//! left out, and come back as NaN.
+//!
+//! The CSV form is one long table with the columns
+//! `table,moves,p1goal,p2goal,goal,square,value`, where each row fills in
+//! the columns its table is keyed by and leaves the rest empty.
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use strum::IntoEnumIterator;
+
+use crate::best_response::BestResponse;
+use crate::game::*;
+use crate::io;
+use crate::solver::{CFRVariant, InfoStateRegrets, CFR};
+use crate::strategy::Strategy;
+use crate::table::*;
+
+/// The values of the actions of one information state, by square.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ActionEntry {
+    pub moves: String,
+    pub goal: Outcome,
+    pub values: BTreeMap<usize, f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct InfoStateEntry {
+    pub moves: String,
+    pub goal: Outcome,
+    pub value: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MetaStateEntry {
+    pub moves: String,
+    pub p1goal: Outcome,
+    pub p2goal: Outcome,
+    pub value: f64,
+}
+
+/// The portable form of a [`Strategy`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StrategyForm {
+    pub board: String,
+    pub probs: Vec<ActionEntry>,
+}
+
+/// The portable form of a [`BestResponse`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BestResponseForm {
+    pub board: String,
+    pub p1_value: Vec<InfoStateEntry>,
+    pub p2_value: Vec<InfoStateEntry>,
+    pub strategy: Vec<ActionEntry>,
+}
+
+/// The portable form of a [`CFR`] solver state.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CFRForm {
+    pub board: String,
+    pub variant: CFRVariant,
+    pub t: usize,
+    pub player_to_update: Option<Player>,
+    pub total_regrets: Vec<ActionEntry>,
+    pub average_strategy: Vec<ActionEntry>,
+    pub expected_value: Vec<MetaStateEntry>,
+    pub counterfactual_probs: Vec<MetaStateEntry>,
+    pub metastate_regrets: Vec<MetaStateEntry>,
+    pub infostate_regrets: Vec<ActionEntry>,
+}
+
+/// The file formats an artifact can be stored in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Bincode,
+    Json,
+    Csv,
+}
+
+impl Format {
+    /// The format a path's extension names.
+    pub fn of_path(path: &Path) -> Option<Format> {
+        match path.extension()?.to_str()? {
+            "bincode" => Some(Format::Bincode),
+            "json" => Some(Format::Json),
+            "csv" => Some(Format::Csv),
+            _ => None,
+        }
+    }
+}
+
+/// Converts state keys to and from [`StateId`]s for one tree.
+pub struct StateKeys<'a> {
+    tree: &'a GameTree,
+    ids: HashMap<String, StateId>,
+}
+
+impl StateKeys<'_> {
+    pub fn new(tree: &GameTree) -> StateKeys<'_> {
+        let ids = (0..tree.states.len())
+            .map(|state| (state_key(&tree.states[state]), state))
+            .collect();
+        StateKeys { tree, ids }
+    }
+
+    pub fn key(&self, state: StateId) -> String {
+        state_key(&self.tree.states[state])
+    }
+
+    pub fn state(&self, key: &str) -> Result<StateId, String> {
+        self.ids.get(key.trim()).copied().ok_or_else(|| {
+            format!(
+                "\"{}\" is not a state of the {} game tree",
+                key, self.tree.board
+            )
+        })
+    }
+
+    /// The index of the action out of `state` that plays on `square`.
+    fn action(&self, state: StateId, square: usize) -> Result<usize, String> {
+        self.tree.children[state]
+            .iter()
+            .position(|&child| self.tree.states[child].last_move() == Some(square - 1))
+            .ok_or_else(|| {
+                format!(
+                    "square {} is not a move from \"{}\"",
+                    square,
+                    self.key(state)
+                )
+            })
+    }
+
+    fn square(&self, state: StateId, action: usize) -> usize {
+        let child = self.tree.children[state][action];
+        self.tree.states[child].last_move().unwrap() + 1
+    }
+
+    fn check_board(&self, board: &str) -> Result<(), String> {
+        if board != self.tree.board.to_string() {
+            return Err(format!(
+                "the file is for a {} board, not {}",
+                board, self.tree.board
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn action_entries(&self, table: &ActionTable) -> Vec<ActionEntry> {
+        table
+            .iter()
+            .filter(|(_, values)| !values.is_empty())
+            .map(|(infostate, values)| ActionEntry {
+                moves: self.key(infostate.state),
+                goal: infostate.goal,
+                values: values
+                    .iter()
+                    .enumerate()
+                    .map(|(action, value)| (self.square(infostate.state, action), *value))
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Rebuild an action table.  Actions missing from `entries` are 0, and
+    /// an empty list gives an empty table, like a solver state saved before
+    /// its first iteration.
+    pub fn action_table(&self, entries: &[ActionEntry]) -> Result<ActionTable, String> {
+        if entries.is_empty() {
+            return Ok(ActionTable::default());
+        }
+        let mut table = ActionTable::new(self.tree, 0.0);
+        for entry in entries {
+            let infostate = InfoState {
+                state: self.state(&entry.moves)?,
+                goal: entry.goal,
+            };
+            for (&square, &value) in entry.values.iter() {
+                let action = self.action(infostate.state, square)?;
+                table[&infostate][action] = value;
+            }
+        }
+        Ok(table)
+    }
+
+    pub fn infostate_entries(&self, table: &InfoStateTable) -> Vec<InfoStateEntry> {
+        let mut result = Vec::new();
+        for goal in Outcome::iter() {
+            for state in 0..self.tree.states.len() {
+                let value = table.get(&InfoState { state, goal });
+                if let Some(value) = value.filter(|value| !value.is_nan()) {
+                    result.push(InfoStateEntry {
+                        moves: self.key(state),
+                        goal,
+                        value,
+                    });
+                }
+            }
+        }
+        result
+    }
+
+    pub fn infostate_table(&self, entries: &[InfoStateEntry]) -> Result<InfoStateTable, String> {
+        if entries.is_empty() {
+            return Ok(InfoStateTable::default());
+        }
+        let mut table = InfoStateTable::new(self.tree, f64::NAN);
+        for entry in entries {
+            let state = self.state(&entry.moves)?;
+            table[&InfoState {
+                state,
+                goal: entry.goal,
+            }] = entry.value;
+        }
+        Ok(table)
+    }
+
+    pub fn metastate_entries(&self, table: &MetaStateTable) -> Vec<MetaStateEntry> {
+        let mut result = Vec::new();
+        for p1goal in Outcome::iter() {
+            for p2goal in Outcome::iter() {
+                for state in 0..self.tree.states.len() {
+                    let value = table.get(&MetaState {
+                        state,
+                        p1goal,
+                        p2goal,
+                    });
+                    if let Some(value) = value.filter(|value| !value.is_nan()) {
+                        result.push(MetaStateEntry {
+                            moves: self.key(state),
+                            p1goal,
+                            p2goal,
+                            value,
+                        });
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    pub fn metastate_table(&self, entries: &[MetaStateEntry]) -> Result<MetaStateTable, String> {
+        if entries.is_empty() {
+            return Ok(MetaStateTable::default());
+        }
+        let mut table = MetaStateTable::new(self.tree, f64::NAN);
+        for entry in entries {
+            table[&MetaState {
+                state: self.state(&entry.moves)?,
+                p1goal: entry.p1goal,
+                p2goal: entry.p2goal,
+            }] = entry.value;
+        }
+        Ok(table)
+    }
+}
+
+/// The squares of a state's moves in order, counting from 1.
+pub fn state_key(state: &State) -> String {
+    let cells = state.cells();
+    let num_moves = cells.iter().copied().max().unwrap_or(0);
+    (1..=num_moves)
+        .map(|move_num| {
+            let square = cells.iter().position(|&m| m == move_num).unwrap();
+            (square + 1).to_string()
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// An artifact with a portable form.
+pub trait Portable: Sized + Serialize + DeserializeOwned {
+    type Form: Serialize + DeserializeOwned;
+
+    fn to_form(&self, keys: &StateKeys) -> Self::Form;
+    fn from_form(form: &Self::Form, keys: &StateKeys) -> Result<Self, String>;
+
+    /// The named tables of the CSV form.
+    fn to_tables(form: Self::Form) -> Vec<(&'static str, Table)>;
+    /// Rebuild the portable form from CSV tables, if the CSV form holds
+    /// everything.
+    fn from_tables(board: String, tables: HashMap<String, Table>) -> Result<Self::Form, String>;
+}
+
+/// One table of the CSV form.
+#[derive(Debug, Clone, Default)]
+pub enum Table {
+    #[default]
+    Empty,
+    Action(Vec<ActionEntry>),
+    InfoState(Vec<InfoStateEntry>),
+    MetaState(Vec<MetaStateEntry>),
+}
+
+impl Table {
+    fn into_actions(self) -> Result<Vec<ActionEntry>, String> {
+        match self {
+            Table::Empty => Ok(Vec::new()),
+            Table::Action(entries) => Ok(entries),
+            _ => Err("expected a table with a square for each row".to_string()),
+        }
+    }
+
+    fn into_infostates(self) -> Result<Vec<InfoStateEntry>, String> {
+        match self {
+            Table::Empty => Ok(Vec::new()),
+            Table::InfoState(entries) => Ok(entries),
+            _ => Err("expected a table with a goal for each row".to_string()),
+        }
+    }
+}
+
+fn take(tables: &mut HashMap<String, Table>, name: &str) -> Table {
+    tables.remove(name).unwrap_or_default()
+}
+
+impl Portable for Strategy {
+    type Form = StrategyForm;
+
+    fn to_form(&self, keys: &StateKeys) -> StrategyForm {
+        StrategyForm {
+            board: keys.tree.board.to_string(),
+            probs: keys.action_entries(&self.probs),
+        }
+    }
+
+    fn from_form(form: &StrategyForm, keys: &StateKeys) -> Result<Strategy, String> {
+        keys.check_board(&form.board)?;
+        Ok(Strategy {
+            probs: keys.action_table(&form.probs)?,
+        })
+    }
+
+    fn to_tables(form: StrategyForm) -> Vec<(&'static str, Table)> {
+        vec![("probs", Table::Action(form.probs))]
+    }
+
+    fn from_tables(
+        board: String,
+        mut tables: HashMap<String, Table>,
+    ) -> Result<StrategyForm, String> {
+        Ok(StrategyForm {
+            board,
+            probs: take(&mut tables, "probs").into_actions()?,
+        })
+    }
+}
+
+impl Portable for BestResponse {
+    type Form = BestResponseForm;
+
+    fn to_form(&self, keys: &StateKeys) -> BestResponseForm {
+        BestResponseForm {
+            board: keys.tree.board.to_string(),
+            p1_value: keys.infostate_entries(&self.p1_value),
+            p2_value: keys.infostate_entries(&self.p2_value),
+            strategy: keys.action_entries(&self.strategy.probs),
+        }
+    }
+
+    fn from_form(form: &BestResponseForm, keys: &StateKeys) -> Result<BestResponse, String> {
+        keys.check_board(&form.board)?;
+        Ok(BestResponse {
+            p1_value: keys.infostate_table(&form.p1_value)?,
+            p2_value: keys.infostate_table(&form.p2_value)?,
+            strategy: Strategy {
+                probs: keys.action_table(&form.strategy)?,
+            },
+        })
+    }
+
+    fn to_tables(form: BestResponseForm) -> Vec<(&'static str, Table)> {
+        vec![
+            ("p1_value", Table::InfoState(form.p1_value)),
+            ("p2_value", Table::InfoState(form.p2_value)),
+            ("strategy", Table::Action(form.strategy)),
+        ]
+    }
+
+    fn from_tables(
+        board: String,
+        mut tables: HashMap<String, Table>,
+    ) -> Result<BestResponseForm, String> {
+        Ok(BestResponseForm {
+            board,
+            p1_value: take(&mut tables, "p1_value").into_infostates()?,
+            p2_value: take(&mut tables, "p2_value").into_infostates()?,
+            strategy: take(&mut tables, "strategy").into_actions()?,
+        })
+    }
+}
+
+impl Portable for CFR {
+    type Form = CFRForm;
+
+    fn to_form(&self, keys: &StateKeys) -> CFRForm {
+        CFRForm {
+            board: keys.tree.board.to_string(),
+            variant: self.variant.clone(),
+            t: self.t,
+            player_to_update: self.player_to_update,
+            total_regrets: keys.action_entries(&self.total_regrets.0),
+            average_strategy: keys.action_entries(&self.average_strategy.probs),
+            expected_value: keys.metastate_entries(&self.expected_value),
+            counterfactual_probs: keys.metastate_entries(&self.counterfactual_probs),
+            metastate_regrets: keys.metastate_entries(&self.metastate_regrets),
+            infostate_regrets: keys.action_entries(&self.infostate_regrets.0),
+        }
+    }
+
+    fn from_form(form: &CFRForm, keys: &StateKeys) -> Result<CFR, String> {
+        keys.check_board(&form.board)?;
+        Ok(CFR {
+            variant: form.variant.clone(),
+            total_regrets: InfoStateRegrets(keys.action_table(&form.total_regrets)?),
+            average_strategy: Strategy {
+                probs: keys.action_table(&form.average_strategy)?,
+            },
+            t: form.t,
+            expected_value: keys.metastate_table(&form.expected_value)?,
+            counterfactual_probs: keys.metastate_table(&form.counterfactual_probs)?,
+            metastate_regrets: keys.metastate_table(&form.metastate_regrets)?,
+            infostate_regrets: InfoStateRegrets(keys.action_table(&form.infostate_regrets)?),
+            player_to_update: form.player_to_update,
+        })
+    }
+
+    fn to_tables(form: CFRForm) -> Vec<(&'static str, Table)> {
+        vec![
+            ("total_regrets", Table::Action(form.total_regrets)),
+            ("average_strategy", Table::Action(form.average_strategy)),
+            ("expected_value", Table::MetaState(form.expected_value)),
+            (
+                "counterfactual_probs",
+                Table::MetaState(form.counterfactual_probs),
+            ),
+            (
+                "metastate_regrets",
+                Table::MetaState(form.metastate_regrets),
+            ),
+            ("infostate_regrets", Table::Action(form.infostate_regrets)),
+        ]
+    }
+
+    fn from_tables(_: String, _: HashMap<String, Table>) -> Result<CFRForm, String> {
+        Err(
+            "the CSV form of a solver state leaves out its variant and iteration, use JSON instead"
+                .to_string(),
+        )
+    }
+}
+
+const CSV_HEADER: &str = "table,moves,p1goal,p2goal,goal,square,value";
+
+fn write_csv<W: Write>(out: &mut W, tables: Vec<(&'static str, Table)>) -> std::io::Result<()> {
+    writeln!(out, "{}", CSV_HEADER)?;
+    for (name, table) in tables {
+        match table {
+            Table::Empty => {}
+            Table::Action(entries) => {
+                for entry in entries {
+                    for (square, value) in entry.values {
+                        writeln!(
+                            out,
+                            "{},{},,,{},{},{}",
+                            name, entry.moves, entry.goal, square, value
+                        )?;
+                    }
+                }
+            }
+            Table::InfoState(entries) => {
+                for entry in entries {
+                    writeln!(
+                        out,
+                        "{},{},,,{},,{}",
+                        name, entry.moves, entry.goal, entry.value
+                    )?;
+                }
+            }
+            Table::MetaState(entries) => {
+                for entry in entries {
+                    writeln!(
+                        out,
+                        "{},{},{},{},,,{}",
+                        name, entry.moves, entry.p1goal, entry.p2goal, entry.value
+                    )?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn parse_goal(s: &str) -> Result<Outcome, String> {
+    Outcome::iter()
+        .find(|goal| goal.to_string() == s)
+        .ok_or_else(|| format!("bad goal \"{}\"", s))
+}
+
+fn parse_number<T: std::str::FromStr>(s: &str) -> Result<T, String> {
+    s.parse().map_err(|_| format!("bad number \"{}\"", s))
+}
+
+fn read_csv(path: &Path) -> Result<HashMap<String, Table>, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mut tables: HashMap<String, Table> = HashMap::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| e.to_string())?;
+        if i == 0 {
+            if line.trim() != CSV_HEADER {
+                return Err(format!("expected the header {}", CSV_HEADER));
+            }
+            continue;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        let row = |e: String| format!("line {}: {}", i + 1, e);
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let [name, moves, p1goal, p2goal, goal, square, value] = fields[..] else {
+            return Err(row("expected 7 columns".to_string()));
+        };
+        let value = parse_number::<f64>(value).map_err(row)?;
+        let table = tables.entry(name.to_string()).or_default();
+        match (table, p1goal.is_empty(), square.is_empty()) {
+            (table @ (Table::Empty | Table::MetaState(_)), false, _) => {
+                let entry = MetaStateEntry {
+                    moves: moves.to_string(),
+                    p1goal: parse_goal(p1goal).map_err(row)?,
+                    p2goal: parse_goal(p2goal).map_err(row)?,
+                    value,
+                };
+                match table {
+                    Table::MetaState(entries) => entries.push(entry),
+                    table => *table = Table::MetaState(vec![entry]),
+                }
+            }
+            (table @ (Table::Empty | Table::Action(_)), true, false) => {
+                let goal = parse_goal(goal).map_err(row)?;
+                let square = parse_number::<usize>(square).map_err(row)?;
+                if !matches!(table, Table::Action(_)) {
+                    *table = Table::Action(Vec::new());
+                }
+                let Table::Action(entries) = table else {
+                    unreachable!()
+                };
+                // Rows of one information state are written together.
+                match entries.last_mut() {
+                    Some(last) if last.moves == moves && last.goal == goal => {
+                        last.values.insert(square, value);
+                    }
+                    _ => entries.push(ActionEntry {
+                        moves: moves.to_string(),
+                        goal,
+                        values: BTreeMap::from([(square, value)]),
+                    }),
+                }
+            }
+            (table @ (Table::Empty | Table::InfoState(_)), true, true) => {
+                let entry = InfoStateEntry {
+                    moves: moves.to_string(),
+                    goal: parse_goal(goal).map_err(row)?,
+                    value,
+                };
+                match table {
+                    Table::InfoState(entries) => entries.push(entry),
+                    table => *table = Table::InfoState(vec![entry]),
+                }
+            }
+            _ => {
+                return Err(row(format!(
+                    "rows of table {} have different columns",
+                    name
+                )))
+            }
+        }
+    }
+    Ok(tables)
+}
+
+/// Load an artifact in the format its extension names.
+pub fn load<T: Portable>(path: &Path, tree: &GameTree) -> Result<T, String> {
+    let keys = StateKeys::new(tree);
+    let form = match Format::of_path(path) {
+        Some(Format::Bincode) => return io::load(path).map_err(|e| e.to_string()),
+        Some(Format::Json) => {
+            let file = File::open(path).map_err(|e| e.to_string())?;
+            serde_json::from_reader(BufReader::new(file)).map_err(|e| e.to_string())?
+        }
+        Some(Format::Csv) => T::from_tables(tree.board.to_string(), read_csv(path)?)?,
+        None => {
+            return Err(format!(
+                "{} doesn't end in .bincode, .json or .csv",
+                path.display()
+            ))
+        }
+    };
+    T::from_form(&form, &keys)
+}
+
+/// Save an artifact in the format its extension names.
+pub fn save<T: Portable>(path: &Path, value: &T, tree: &GameTree) -> Result<(), String> {
+    let format = Format::of_path(path)
+        .ok_or_else(|| format!("{} doesn't end in .bincode, .json or .csv", path.display()))?;
+    if format == Format::Bincode {
+        return io::save(path, value).map_err(|e| e.to_string());
+    }
+    let form = value.to_form(&StateKeys::new(tree));
+    let mut out = BufWriter::new(File::create(path).map_err(|e| e.to_string())?);
+    match format {
+        Format::Json => serde_json::to_writer(&mut out, &form).map_err(|e| e.to_string())?,
+        _ => write_csv(&mut out, T::to_tables(form)).map_err(|e| e.to_string())?,
+    }
+    out.flush().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Save `value` as bincode, convert it to `converted` (a file name whose
+    /// extension picks the format), and save what comes back as bincode
+    /// again.
+    fn bincode_round_trip<T: Portable>(
+        value: &T,
+        tree: &GameTree,
+        converted: &str,
+    ) -> (Vec<u8>, Vec<u8>) {
+        let dir = std::env::temp_dir().join(format!("tictac-portable-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let original = dir.join(format!("{}.bincode", converted));
+        let back = dir.join(format!("{}.back.bincode", converted));
+        let converted = dir.join(converted);
+        save(&original, value, tree).unwrap();
+        save(&converted, &load::<T>(&original, tree).unwrap(), tree).unwrap();
+        save(&back, &load::<T>(&converted, tree).unwrap(), tree).unwrap();
+        let bytes = (
+            std::fs::read(&original).unwrap(),
+            std::fs::read(&back).unwrap(),
+        );
+        for path in [original, converted, back] {
+            std::fs::remove_file(path).unwrap();
+        }
+        bytes
+    }
+
+    fn solve(tree: &GameTree) -> (CFR, Strategy) {
+        let mut cfr = CFR::new(CFRVariant::Plus, true);
+        let mut strategy = Strategy::uniform(tree);
+        for _ in 0..5 {
+            strategy = cfr.cfr_round(&strategy, tree, &OutcomeValues::default());
+        }
+        (cfr, strategy)
+    }
+
+    #[test]
+    fn state_keys_round_trip() {
+        let tree = GameTree::with_board("2x3:3".parse().unwrap());
+        let keys = StateKeys::new(&tree);
+        assert_eq!(keys.key(0), "");
+        for state in 0..tree.states.len() {
+            assert_eq!(keys.state(&keys.key(state)), Ok(state));
+        }
+    }
+
+    #[test]
+    fn strategy_csv_round_trip() {
+        let tree = GameTree::with_board("2x3:3".parse().unwrap());
+        let (_, strategy) = solve(&tree);
+        let (original, back) = bincode_round_trip(&strategy, &tree, "strategy.csv");
+        assert_eq!(original, back);
+    }
+
+    #[test]
+    fn strategy_json_round_trip() {
+        let tree = GameTree::with_board("2x3:3".parse().unwrap());
+        let (_, strategy) = solve(&tree);
+        let (original, back) = bincode_round_trip(&strategy, &tree, "strategy.json");
+        assert_eq!(original, back);
+    }
+
+    #[test]
+    fn cfr_json_round_trip() {
+        let tree = GameTree::with_board("2x3:3".parse().unwrap());
+        let (cfr, _) = solve(&tree);
+        let (original, back) = bincode_round_trip(&cfr, &tree, "cfr.json");
+        assert_eq!(original, back);
+    }
+}
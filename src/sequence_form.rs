@@ -0,0 +1,478 @@
+//! Exact equilibria of a single round, by solving the sequence form linear
+//! program of [Koller, Megiddo and von
+//! Stengel](https://www.sciencedirect.com/science/article/pii/S0899825696900515)
+//! with the exact [`simplex`](crate::simplex) method.
+//!
+//! The program has a variable for every sequence of moves of one player,
+//! which is tens of thousands on the usual 3x3 board, far too many to solve
+//! at once.  Instead each player is restricted to the moves a short CFR
+//! solve plays, and those restrictions are widened by best responses until
+//! the equilibrium of the restricted round is one of the whole round, as in
+//! the double oracle method.  The 3x3 round takes a few minutes this way.
+
+use num_rational::BigRational;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use std::collections::HashMap;
+use strum::IntoEnumIterator;
+
+use crate::game::*;
+use crate::simplex::*;
+use crate::solver::{CFRVariant, CFR};
+use crate::strategy::Strategy;
+use crate::table::*;
+
+/// An exact equilibrium of a round.
+#[derive(Debug, Clone)]
+pub struct ExactSolution {
+    /// The first player's expected value, averaged over how the goals are
+    /// dealt.
+    pub value: BigRational,
+    /// An equilibrium strategy for both players, rounded to `f64`.
+    /// Information states that the equilibrium never reaches are uniform.
+    pub strategy: Strategy,
+}
+
+/// Solve the round described by `tree` and `outcome_values` exactly.  The
+/// payoffs are taken to be exactly the `f64` values of `outcome_values`, and
+/// the goal probabilities exactly the ratios of the distribution's weights.
+pub fn solve_exact(
+    tree: &GameTree,
+    outcome_values: &OutcomeValues,
+) -> Result<ExactSolution, LpError> {
+    let sequences = [
+        Sequences::new(tree, Player::Player1),
+        Sequences::new(tree, Player::Player2),
+    ];
+    let payoffs = payoff_matrix(tree, outcome_values, &sequences);
+    // Every payoff is raised by `payoffs.bound` so none are negative, which
+    // `best_guarantee` relies on.  This raises the value by exactly
+    // `payoffs.bound`.
+    let own_payoffs = [Player::Player1, Player::Player2].map(|player| {
+        payoffs
+            .entries
+            .iter()
+            .map(|(&(p1_seq, p2_seq), (payoff, prob))| {
+                let shift = prob * &payoffs.bound;
+                match player {
+                    Player::Player1 => (p1_seq, p2_seq, payoff + shift),
+                    Player::Player2 => (p2_seq, p1_seq, shift - payoff),
+                }
+            })
+            .collect::<Vec<_>>()
+    });
+
+    // Solving the whole program at once is far too slow for the usual 3x3
+    // board, so each player is restricted to the moves a short CFR solve
+    // plays, and the restrictions are widened by best responses to the
+    // equilibrium of the restricted round until neither player has a better
+    // response left out.
+    let guess = guess_equilibrium(tree, outcome_values);
+    let mut supports = sequences.each_ref().map(|own| own.likely_moves(&guess));
+    loop {
+        let played = [0, 1].map(|player| sequences[player].played(&supports[player]));
+        let mut plans = Vec::new();
+        let mut values = Vec::new();
+        for player in [Player::Player1, Player::Player2] {
+            let (value, plan) = best_guarantee(
+                &sequences[player as usize],
+                &sequences[player.opponent() as usize],
+                &own_payoffs[player as usize],
+                &played[player as usize],
+                &played[player.opponent() as usize],
+            )?;
+            plans.push(plan);
+            values.push(value - &payoffs.bound);
+        }
+        // Zero-sum: what the first player can guarantee is exactly what the
+        // second player can hold them to.
+        assert_eq!(values[0], -values[1].clone());
+
+        let mut widened = false;
+        let mut bounds = Vec::new();
+        for player in [Player::Player1, Player::Player2] {
+            let (value, response) = sequences[player as usize].best_response(
+                &own_payoffs[player as usize],
+                &plans[player.opponent() as usize],
+                &supports[player as usize],
+            );
+            bounds.push(value - &payoffs.bound);
+            for seq in response {
+                widened |= !supports[player as usize][seq];
+                supports[player as usize][seq] = true;
+            }
+        }
+        log::info!(
+            "With {} and {} sequences the value is between {} and {}",
+            played[0].iter().filter(|&&p| p).count(),
+            played[1].iter().filter(|&&p| p).count(),
+            (-bounds[1].clone()).to_f64().unwrap(),
+            bounds[0].to_f64().unwrap()
+        );
+        if !widened {
+            // Neither player can do better than the restricted equilibrium
+            // by playing anything else, so it is an equilibrium of the whole
+            // round.
+            let mut strategy = Strategy {
+                probs: ActionTable::new(tree, 0.0),
+            };
+            for player in [Player::Player1, Player::Player2] {
+                sequences[player as usize].fill_strategy(&plans[player as usize], &mut strategy);
+            }
+            return Ok(ExactSolution {
+                value: values.remove(0),
+                strategy,
+            });
+        }
+    }
+}
+
+/// How many iterations of CFR+ to run to guess which moves the equilibrium
+/// plays.
+const GUESS_ITERATIONS: usize = 200;
+
+/// How often the guess has to play a move for the restricted round to
+/// include it.
+const GUESS_THRESHOLD: f64 = 0.01;
+
+/// The average strategy of a short CFR+ solve.
+fn guess_equilibrium(tree: &GameTree, outcome_values: &OutcomeValues) -> Strategy {
+    log::info!(
+        "Guessing the equilibrium with {} iterations of CFR+...",
+        GUESS_ITERATIONS
+    );
+    let mut cfr = CFR::new(CFRVariant::Plus, true);
+    let mut strategy = Strategy::uniform(tree);
+    for _ in 0..GUESS_ITERATIONS {
+        strategy = cfr.cfr_round(&strategy, tree, outcome_values);
+    }
+    cfr.average_strategy
+}
+
+/// The sequences of one player.  Sequence 0 is the empty sequence, and the
+/// moves out of each of the player's information states are numbered
+/// consecutively.
+struct Sequences {
+    num_sequences: usize,
+    /// The sequence of the first move out of each information state of the
+    /// player, indexed like an [`InfoStateTable`].
+    first_sequence: Vec<Option<usize>>,
+    num_states: usize,
+    /// The player's information states, each with the sequence leading to
+    /// it, the sequence of its first move and its number of moves.
+    infostates: Vec<(InfoState, usize, usize, usize)>,
+    /// The player's last move before reaching each state, as the state it
+    /// was made in and the index of the move.
+    last_move: Vec<Option<(StateId, usize)>>,
+}
+
+impl Sequences {
+    fn new(tree: &GameTree, player: Player) -> Sequences {
+        let num_states = tree.states.len();
+        let mut last_move = vec![None; num_states];
+        for state in 0..num_states {
+            for (index, &child) in tree.children[state].iter().enumerate() {
+                last_move[child] = if tree.current_player[state] == player {
+                    Some((state, index))
+                } else {
+                    last_move[state]
+                };
+            }
+        }
+
+        let mut sequences = Sequences {
+            num_sequences: 1,
+            first_sequence: vec![None; num_states * 3],
+            num_states,
+            infostates: Vec::new(),
+            last_move,
+        };
+        for goal in Outcome::iter() {
+            for state in 0..num_states {
+                let num_moves = tree.children[state].len();
+                if tree.current_player[state] != player || num_moves == 0 {
+                    continue;
+                }
+                let infostate = InfoState { state, goal };
+                let parent = sequences.sequence_before(goal, state);
+                let first = sequences.num_sequences;
+                sequences.first_sequence[goal.index() * num_states + state] = Some(first);
+                sequences
+                    .infostates
+                    .push((infostate, parent, first, num_moves));
+                sequences.num_sequences += num_moves;
+            }
+        }
+        sequences
+    }
+
+    /// The player's sequence of moves that leads to `state` with `goal`.
+    /// States are numbered so that parents come first, so this only needs
+    /// the sequences of earlier states.
+    fn sequence_before(&self, goal: Outcome, state: StateId) -> usize {
+        match self.last_move[state] {
+            None => 0,
+            Some((parent, index)) => {
+                self.first_sequence[goal.index() * self.num_states + parent].unwrap() + index
+            }
+        }
+    }
+
+    /// The moves that `strategy` plays at least [`GUESS_THRESHOLD`] of the
+    /// time, and the one it plays most out of each information state.
+    fn likely_moves(&self, strategy: &Strategy) -> Vec<bool> {
+        let mut support = vec![false; self.num_sequences];
+        support[0] = true;
+        for (infostate, _, first, _) in self.infostates.iter() {
+            let probs = &strategy.probs[infostate];
+            let most_likely = (0..probs.len())
+                .reduce(|best, index| {
+                    if probs[index] > probs[best] {
+                        index
+                    } else {
+                        best
+                    }
+                })
+                .unwrap();
+            for (index, prob) in probs.iter().enumerate() {
+                support[first + index] = *prob >= GUESS_THRESHOLD || index == most_likely;
+            }
+        }
+        support
+    }
+
+    /// Which sequences can be played when only playing the moves in
+    /// `support`, which must have a move out of every information state it
+    /// reaches.
+    fn played(&self, support: &[bool]) -> Vec<bool> {
+        let mut played = vec![false; self.num_sequences];
+        played[0] = true;
+        for (_, parent, first, num_moves) in self.infostates.iter() {
+            if played[*parent] {
+                let moves = *first..first + num_moves;
+                played[moves.clone()].copy_from_slice(&support[moves]);
+            }
+        }
+        played
+    }
+
+    /// Set the player's moves in `strategy` from the realization plan
+    /// `plan`.
+    fn fill_strategy(&self, plan: &[BigRational], strategy: &mut Strategy) {
+        for (infostate, parent, first, num_moves) in self.infostates.iter() {
+            let probs = &mut strategy.probs[infostate];
+            if plan[*parent].is_zero() {
+                probs.fill(1.0 / *num_moves as f64);
+                continue;
+            }
+            for (index, prob) in probs.iter_mut().enumerate() {
+                *prob = (&plan[first + index] / &plan[*parent]).to_f64().unwrap();
+            }
+        }
+    }
+
+    /// The value of a pure best response to the opponent's realization plan
+    /// `opponent_plan`, and its sequences, with `payoffs` as for
+    /// [`best_guarantee`].  Among equally good moves it plays one in
+    /// `support` if it can.
+    fn best_response(
+        &self,
+        payoffs: &[(usize, usize, BigRational)],
+        opponent_plan: &[BigRational],
+        support: &[bool],
+    ) -> (BigRational, Vec<usize>) {
+        let mut values = vec![BigRational::zero(); self.num_sequences];
+        for (own_seq, opponent_seq, payoff) in payoffs {
+            if !opponent_plan[*opponent_seq].is_zero() {
+                values[*own_seq] += payoff * &opponent_plan[*opponent_seq];
+            }
+        }
+        // Information states come after the one their sequence starts from,
+        // so working backwards finds the best move of each before its
+        // parent's.
+        let mut best_moves = vec![0; self.infostates.len()];
+        for (index, (_, parent, first, num_moves)) in self.infostates.iter().enumerate().rev() {
+            let best = (*first..first + num_moves)
+                .max_by(|&a, &b| values[a].cmp(&values[b]).then(support[a].cmp(&support[b])))
+                .unwrap();
+            best_moves[index] = best;
+            let value = values[best].clone();
+            values[*parent] += value;
+        }
+        let mut played = vec![false; self.num_sequences];
+        played[0] = true;
+        for (index, (_, parent, _, _)) in self.infostates.iter().enumerate() {
+            if played[*parent] {
+                played[best_moves[index]] = true;
+            }
+        }
+        let sequences = (0..self.num_sequences).filter(|&seq| played[seq]).collect();
+        (values.swap_remove(0), sequences)
+    }
+}
+
+struct PayoffMatrix {
+    /// For each pair of sequences that ends the round, the first player's
+    /// payoff weighted by the probability of the goals, and that
+    /// probability.
+    entries: HashMap<(usize, usize), (BigRational, BigRational)>,
+    /// The largest payoff to either player at the end of the round.
+    bound: BigRational,
+}
+
+fn payoff_matrix(
+    tree: &GameTree,
+    outcome_values: &OutcomeValues,
+    sequences: &[Sequences; 2],
+) -> PayoffMatrix {
+    let rational = |x: f64| BigRational::from_float(x).expect("payoffs must be finite");
+    let weights = tree.goals.weights();
+    let total_weight: BigRational = weights.iter().map(|w| rational(*w)).sum();
+
+    let mut payoffs = PayoffMatrix {
+        entries: HashMap::new(),
+        bound: BigRational::zero(),
+    };
+    for p1goal in Outcome::iter() {
+        for p2goal in Outcome::iter() {
+            let weight = weights[goal_pair_index(p1goal, p2goal)];
+            if weight == 0.0 {
+                continue;
+            }
+            let prob = rational(weight) / &total_weight;
+            for state in 0..tree.states.len() {
+                let metastate = MetaState {
+                    state,
+                    p1goal,
+                    p2goal,
+                };
+                let outcomes = match metastate.outcomes(tree) {
+                    Some(outcomes) => outcomes,
+                    None => continue,
+                };
+                let payoff = rational(outcome_values.evaluate(&metastate, tree, outcomes));
+                let key = (
+                    sequences[0].sequence_before(p1goal, state),
+                    sequences[1].sequence_before(p2goal, state),
+                );
+                if payoff.abs() > payoffs.bound {
+                    payoffs.bound = payoff.abs();
+                }
+                let (sum, total_prob) = payoffs
+                    .entries
+                    .entry(key)
+                    .or_insert_with(|| (BigRational::zero(), BigRational::zero()));
+                *sum += &prob * payoff;
+                *total_prob += &prob;
+            }
+        }
+    }
+    payoffs
+}
+
+/// The most `own` can guarantee when only playing the sequences in
+/// `own_played` against any strategy of `opponent` that only plays the
+/// sequences in `opponent_played`, and a realization plan that guarantees
+/// it.  `payoffs` has an entry `(own_seq, opponent_seq, payoff)` for every
+/// pair of sequences that ends the round, with `own`'s payoff, which must not
+/// be negative.
+fn best_guarantee(
+    own: &Sequences,
+    opponent: &Sequences,
+    payoffs: &[(usize, usize, BigRational)],
+    own_played: &[bool],
+    opponent_played: &[bool],
+) -> Result<(BigRational, Vec<BigRational>), LpError> {
+    // Variables are the realization plan of `own` on the sequences it plays,
+    // followed by the value of each information state of `opponent` (the
+    // empty one being the whole round) that both players can reach.
+    // Payoffs are not negative, so neither are the values.
+    let mut own_vars = vec![None; own.num_sequences];
+    let mut num_own_vars = 0;
+    for (seq, var) in own_vars.iter_mut().enumerate() {
+        if own_played[seq] {
+            *var = Some(num_own_vars);
+            num_own_vars += 1;
+        }
+    }
+    let own_infostates = own
+        .infostates
+        .iter()
+        .filter(|(_, parent, _, _)| own_played[*parent])
+        .map(|(_, parent, first, num_moves)| {
+            let moves = (*first..first + num_moves)
+                .filter(|&seq| own_played[seq])
+                .collect::<Vec<_>>();
+            (*parent, moves)
+        })
+        .collect::<Vec<_>>();
+    let is_reached =
+        |state: StateId| Outcome::iter().any(|goal| own_played[own.sequence_before(goal, state)]);
+    let opponent_infostates = opponent
+        .infostates
+        .iter()
+        .filter(|(infostate, parent, _, _)| opponent_played[*parent] && is_reached(infostate.state))
+        .collect::<Vec<_>>();
+    let mut lp = LinearProgram::new(num_own_vars + opponent_infostates.len() + 1);
+
+    lp.add_constraint(
+        vec![(0, BigRational::one())],
+        Relation::Eq,
+        BigRational::one(),
+    );
+    for (parent, moves) in own_infostates.iter() {
+        let mut expr: LinearExpr = moves
+            .iter()
+            .map(|seq| (own_vars[*seq].unwrap(), BigRational::one()))
+            .collect();
+        expr.push((own_vars[*parent].unwrap(), -BigRational::one()));
+        lp.add_constraint(expr, Relation::Eq, BigRational::zero());
+    }
+
+    // For every sequence of the opponent, the value of the information state
+    // it starts from is at most the payoff of the rounds it ends plus the
+    // values of the information states it leads to.  Information states
+    // that `own` never reaches are worth nothing, so are left out.
+    let mut rows: Vec<Option<LinearExpr>> = vec![None; opponent.num_sequences];
+    rows[0] = Some(vec![(num_own_vars, BigRational::one())]);
+    for (index, (_, _, first, num_moves)) in opponent_infostates.iter().enumerate() {
+        let value = num_own_vars + index + 1;
+        for seq in *first..first + num_moves {
+            if opponent_played[seq] {
+                rows[seq] = Some(vec![(value, BigRational::one())]);
+            }
+        }
+    }
+    for (index, (_, parent, _, _)) in opponent_infostates.iter().enumerate() {
+        let value = num_own_vars + index + 1;
+        rows[*parent]
+            .as_mut()
+            .unwrap()
+            .push((value, -BigRational::one()));
+    }
+    for (own_seq, opponent_seq, payoff) in payoffs {
+        if let (Some(var), true) = (own_vars[*own_seq], opponent_played[*opponent_seq]) {
+            rows[*opponent_seq]
+                .as_mut()
+                .unwrap()
+                .push((var, -payoff.clone()));
+        }
+    }
+    for row in rows.into_iter().flatten() {
+        lp.add_constraint(row, Relation::LessEq, BigRational::zero());
+    }
+
+    // Always playing the first move played, with every value 0, is feasible
+    // since no payoff is negative.  Starting there saves a long, degenerate
+    // phase one.
+    let mut basis = vec![Some(0)];
+    basis.extend(own_infostates.iter().map(|(_, moves)| own_vars[moves[0]]));
+    basis.resize(lp.num_constraints(), None);
+    let solution = lp.maximize_from(&vec![(num_own_vars, BigRational::one())], &basis)?;
+    let mut plan = vec![BigRational::zero(); own.num_sequences];
+    for (seq, var) in own_vars.iter().enumerate() {
+        if let Some(var) = var {
+            plan[seq] = solution.values[*var].clone();
+        }
+    }
+    Ok((solution.objective, plan))
+}
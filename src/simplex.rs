@@ -0,0 +1,529 @@
+//! A two-phase simplex method over arbitrary precision rationals, so that
+//! small linear programs can be solved exactly.
+//!
+//! The tableau is stored as sparse rows, which suits the very sparse
+//! programs that come from game trees.  Pivots use Dantzig's rule, falling
+//! back to Bland's rule after a run of degenerate pivots so that the method
+//! cannot cycle.
+
+use num_rational::BigRational;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+
+/// A sparse linear combination of variables, as `(variable, coefficient)`.
+pub type LinearExpr = Vec<(usize, BigRational)>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Relation {
+    LessEq,
+    Eq,
+    GreaterEq,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LpError {
+    Infeasible,
+    Unbounded,
+}
+
+impl std::fmt::Display for LpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LpError::Infeasible => write!(f, "the linear program is infeasible"),
+            LpError::Unbounded => write!(f, "the linear program is unbounded"),
+        }
+    }
+}
+
+impl std::error::Error for LpError {}
+
+/// An optimal solution of a [`LinearProgram`].
+#[derive(Debug, Clone)]
+pub struct LpSolution {
+    pub objective: BigRational,
+    /// The value of every variable, in the order they were numbered.
+    pub values: Vec<BigRational>,
+}
+
+/// A linear program over variables `0..num_vars`, all of which are
+/// non-negative.
+#[derive(Debug, Clone, Default)]
+pub struct LinearProgram {
+    num_vars: usize,
+    constraints: Vec<(LinearExpr, Relation, BigRational)>,
+}
+
+impl LinearProgram {
+    pub fn new(num_vars: usize) -> LinearProgram {
+        LinearProgram {
+            num_vars,
+            constraints: Vec::new(),
+        }
+    }
+
+    pub fn num_vars(&self) -> usize {
+        self.num_vars
+    }
+
+    pub fn num_constraints(&self) -> usize {
+        self.constraints.len()
+    }
+
+    /// Add the constraint `expr relation rhs`.  Repeated variables in `expr`
+    /// are added together.
+    pub fn add_constraint(&mut self, expr: LinearExpr, relation: Relation, rhs: BigRational) {
+        self.constraints.push((expr, relation, rhs));
+    }
+
+    /// Maximize `objective` subject to the constraints.
+    pub fn maximize(&self, objective: &LinearExpr) -> Result<LpSolution, LpError> {
+        let mut tableau = Tableau::new(self);
+        tableau.phase_one()?;
+        tableau.phase_two(self, objective)
+    }
+
+    /// Like [`maximize`](Self::maximize), but starting from a basis with the
+    /// variable `basis[i]` for constraint `i`, or its slack if `None`.  This
+    /// skips phase one, which can take a long time on degenerate programs.
+    /// Falls back to `maximize` if the basis is singular or infeasible.
+    pub fn maximize_from(
+        &self,
+        objective: &LinearExpr,
+        basis: &[Option<usize>],
+    ) -> Result<LpSolution, LpError> {
+        let mut tableau = Tableau::new(self);
+        if tableau.crash(basis) {
+            return tableau.phase_two(self, objective);
+        }
+        self.maximize(objective)
+    }
+}
+
+/// How many degenerate pivots in a row to allow before switching to Bland's
+/// rule.
+const MAX_DEGENERATE_PIVOTS: usize = 50;
+
+struct Tableau {
+    /// Sparse rows, sorted by column, without zeros.
+    rows: Vec<LinearExpr>,
+    rhs: Vec<BigRational>,
+    /// The basic variable of each row.
+    basis: Vec<usize>,
+    /// Structural variables come first, then slacks, then artificials.
+    artificial_start: usize,
+    num_cols: usize,
+    /// Reduced costs of every column for the current objective.
+    reduced_costs: Vec<BigRational>,
+    objective_value: BigRational,
+}
+
+impl Tableau {
+    /// The tableau in standard form with a basis of slacks and artificials.
+    fn new(lp: &LinearProgram) -> Tableau {
+        let num_slacks = lp
+            .constraints
+            .iter()
+            .filter(|(_, relation, _)| *relation != Relation::Eq)
+            .count();
+        let artificial_start = lp.num_vars + num_slacks;
+        let mut next_slack = lp.num_vars;
+        let mut next_artificial = artificial_start;
+        let mut rows = Vec::new();
+        let mut rhs = Vec::new();
+        let mut basis = Vec::new();
+        for (expr, relation, value) in lp.constraints.iter() {
+            let mut row = normalize(expr);
+            let mut value = value.clone();
+            let mut slack = match relation {
+                Relation::LessEq => Some(BigRational::one()),
+                Relation::GreaterEq => Some(-BigRational::one()),
+                Relation::Eq => None,
+            };
+            // Keep the right hand side non-negative so the starting basis is
+            // feasible.
+            if value.is_negative() {
+                value = -value;
+                row.iter_mut().for_each(|(_, c)| *c = -c.clone());
+                slack = slack.map(|s| -s);
+            }
+            match slack {
+                Some(slack) => {
+                    let positive = slack.is_positive();
+                    row.push((next_slack, slack));
+                    if positive {
+                        basis.push(next_slack);
+                    } else {
+                        row.push((next_artificial, BigRational::one()));
+                        basis.push(next_artificial);
+                        next_artificial += 1;
+                    }
+                    next_slack += 1;
+                }
+                None => {
+                    row.push((next_artificial, BigRational::one()));
+                    basis.push(next_artificial);
+                    next_artificial += 1;
+                }
+            }
+            rows.push(row);
+            rhs.push(value);
+        }
+        Tableau {
+            rows,
+            rhs,
+            basis,
+            artificial_start,
+            num_cols: next_artificial,
+            reduced_costs: vec![BigRational::zero(); next_artificial],
+            objective_value: BigRational::zero(),
+        }
+    }
+
+    /// Drive the artificial variables to zero, leaving a feasible basis of
+    /// real variables.
+    fn phase_one(&mut self) -> Result<(), LpError> {
+        let mut phase_one = vec![BigRational::zero(); self.num_cols];
+        phase_one[self.artificial_start..].fill(-BigRational::one());
+        self.set_objective(&phase_one);
+        self.optimize(self.num_cols)?;
+        if self.objective_value.is_negative() {
+            return Err(LpError::Infeasible);
+        }
+        self.drop_artificials();
+        Ok(())
+    }
+
+    /// Pivot `basis` in, which may have real variables in place of the
+    /// artificial ones.  Returns whether that gave a feasible basis without
+    /// artificials, in which case they are dropped.
+    fn crash(&mut self, basis: &[Option<usize>]) -> bool {
+        for (row, var) in basis.iter().enumerate() {
+            if let Some(var) = var {
+                if self.get(row, *var).is_none() {
+                    return false;
+                }
+                self.pivot(row, *var);
+            }
+        }
+        if self.rhs.iter().any(|r| r.is_negative())
+            || self.basis.iter().any(|&var| var >= self.artificial_start)
+        {
+            return false;
+        }
+        self.drop_artificials();
+        true
+    }
+
+    /// Maximize `objective` from a feasible basis.
+    fn phase_two(
+        &mut self,
+        lp: &LinearProgram,
+        objective: &LinearExpr,
+    ) -> Result<LpSolution, LpError> {
+        let mut costs = vec![BigRational::zero(); self.num_cols];
+        for (var, coefficient) in objective {
+            costs[*var] += coefficient;
+        }
+        self.set_objective(&costs);
+        self.optimize(self.artificial_start)?;
+
+        let mut values = vec![BigRational::zero(); lp.num_vars];
+        for (row, &var) in self.basis.iter().enumerate() {
+            if var < lp.num_vars {
+                values[var] = self.rhs[row].clone();
+            }
+        }
+        Ok(LpSolution {
+            objective: self.objective_value.clone(),
+            values,
+        })
+    }
+
+    /// Price out the basis for maximizing `costs`.
+    fn set_objective(&mut self, costs: &[BigRational]) {
+        self.reduced_costs = costs.to_vec();
+        self.objective_value = BigRational::zero();
+        for (row, &var) in self.basis.iter().enumerate() {
+            let cost = &costs[var];
+            if cost.is_zero() {
+                continue;
+            }
+            for (col, coefficient) in self.rows[row].iter() {
+                self.reduced_costs[*col] -= cost * coefficient;
+            }
+            self.objective_value += cost * &self.rhs[row];
+        }
+    }
+
+    /// Pivot until no column below `max_col` improves the objective.
+    fn optimize(&mut self, max_col: usize) -> Result<(), LpError> {
+        let mut degenerate_pivots = 0;
+        let mut pivots = 0usize;
+        loop {
+            let improving = (0..max_col).filter(|&col| self.reduced_costs[col].is_positive());
+            let entering = if degenerate_pivots >= MAX_DEGENERATE_PIVOTS {
+                improving.min()
+            } else {
+                improving.max_by(|&a, &b| self.reduced_costs[a].cmp(&self.reduced_costs[b]))
+            };
+            let entering = match entering {
+                Some(col) => col,
+                None => return Ok(()),
+            };
+
+            let mut leaving: Option<(usize, BigRational)> = None;
+            for row in 0..self.rows.len() {
+                let coefficient = match self.get(row, entering) {
+                    Some(c) if c.is_positive() => c,
+                    _ => continue,
+                };
+                let ratio = &self.rhs[row] / coefficient;
+                let better = match &leaving {
+                    None => true,
+                    Some((best_row, best_ratio)) => {
+                        ratio < *best_ratio
+                            || (ratio == *best_ratio && self.basis[row] < self.basis[*best_row])
+                    }
+                };
+                if better {
+                    leaving = Some((row, ratio));
+                }
+            }
+            let (row, ratio) = leaving.ok_or(LpError::Unbounded)?;
+            if ratio.is_zero() {
+                degenerate_pivots += 1;
+            } else {
+                degenerate_pivots = 0;
+            }
+            self.pivot(row, entering);
+            pivots += 1;
+            if pivots.is_multiple_of(1000) {
+                log::debug!(
+                    "{} pivots, objective {}",
+                    pivots,
+                    self.objective_value.to_f64().unwrap_or(f64::NAN)
+                );
+            }
+        }
+    }
+
+    fn get(&self, row: usize, col: usize) -> Option<&BigRational> {
+        let row = &self.rows[row];
+        row.binary_search_by_key(&col, |(c, _)| *c)
+            .ok()
+            .map(|i| &row[i].1)
+    }
+
+    fn pivot(&mut self, pivot_row: usize, pivot_col: usize) {
+        let pivot = self.get(pivot_row, pivot_col).unwrap().clone();
+        for (_, coefficient) in self.rows[pivot_row].iter_mut() {
+            *coefficient /= &pivot;
+        }
+        self.rhs[pivot_row] /= &pivot;
+        let row = std::mem::take(&mut self.rows[pivot_row]);
+        let row_rhs = self.rhs[pivot_row].clone();
+
+        for other in 0..self.rows.len() {
+            if other == pivot_row {
+                continue;
+            }
+            let factor = match self.get(other, pivot_col) {
+                Some(factor) => factor.clone(),
+                None => continue,
+            };
+            self.rows[other] = subtract_multiple(&self.rows[other], &factor, &row);
+            self.rhs[other] -= &factor * &row_rhs;
+        }
+        let factor = self.reduced_costs[pivot_col].clone();
+        if !factor.is_zero() {
+            for (col, coefficient) in row.iter() {
+                self.reduced_costs[*col] -= &factor * coefficient;
+            }
+            self.objective_value += &factor * &row_rhs;
+        }
+
+        self.rows[pivot_row] = row;
+        self.basis[pivot_row] = pivot_col;
+    }
+
+    /// After phase one, pivot the artificials out of the basis, drop rows
+    /// that turned out to be redundant and forget the artificial columns.
+    fn drop_artificials(&mut self) {
+        let mut row = 0;
+        while row < self.rows.len() {
+            if self.basis[row] >= self.artificial_start {
+                let replacement = self.rows[row]
+                    .iter()
+                    .map(|(col, _)| *col)
+                    .find(|&col| col < self.artificial_start);
+                match replacement {
+                    Some(col) => self.pivot(row, col),
+                    None => {
+                        self.rows.remove(row);
+                        self.rhs.remove(row);
+                        self.basis.remove(row);
+                        continue;
+                    }
+                }
+            }
+            row += 1;
+        }
+        let artificial_start = self.artificial_start;
+        for row in self.rows.iter_mut() {
+            row.retain(|(col, _)| *col < artificial_start);
+        }
+        self.num_cols = artificial_start;
+    }
+}
+
+/// `expr` sorted by variable, with repeated variables added together and
+/// zeros removed.
+fn normalize(expr: &LinearExpr) -> LinearExpr {
+    let mut expr = expr.clone();
+    expr.sort_by_key(|(var, _)| *var);
+    let mut result: LinearExpr = Vec::with_capacity(expr.len());
+    for (var, coefficient) in expr {
+        match result.last_mut() {
+            Some((last, sum)) if *last == var => *sum += coefficient,
+            _ => result.push((var, coefficient)),
+        }
+    }
+    result.retain(|(_, coefficient)| !coefficient.is_zero());
+    result
+}
+
+/// `a - factor * b` for sparse rows sorted by column.
+fn subtract_multiple(a: &LinearExpr, factor: &BigRational, b: &LinearExpr) -> LinearExpr {
+    let mut result = Vec::with_capacity(a.len() + b.len());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        let a_col = a.get(i).map(|(c, _)| *c).unwrap_or(usize::MAX);
+        let b_col = b.get(j).map(|(c, _)| *c).unwrap_or(usize::MAX);
+        if a_col < b_col {
+            result.push(a[i].clone());
+            i += 1;
+        } else if b_col < a_col {
+            result.push((b_col, -(factor * &b[j].1)));
+            j += 1;
+        } else {
+            let value = &a[i].1 - factor * &b[j].1;
+            if !value.is_zero() {
+                result.push((a_col, value));
+            }
+            i += 1;
+            j += 1;
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ratio(numer: i64, denom: i64) -> BigRational {
+        BigRational::new(numer.into(), denom.into())
+    }
+
+    fn expr(coefficients: &[(usize, i64)]) -> LinearExpr {
+        coefficients
+            .iter()
+            .map(|&(var, coefficient)| (var, ratio(coefficient, 1)))
+            .collect()
+    }
+
+    #[test]
+    fn small_optimum() {
+        // Maximize 3x + 2y with x + y <= 4, x + 3y <= 6 and x <= 3: the
+        // corner x = 3, y = 1 gives 11.
+        let mut lp = LinearProgram::new(2);
+        lp.add_constraint(expr(&[(0, 1), (1, 1)]), Relation::LessEq, ratio(4, 1));
+        lp.add_constraint(expr(&[(0, 1), (1, 3)]), Relation::LessEq, ratio(6, 1));
+        lp.add_constraint(expr(&[(0, 1)]), Relation::LessEq, ratio(3, 1));
+        let solution = lp.maximize(&expr(&[(0, 3), (1, 2)])).unwrap();
+        assert_eq!(solution.objective, ratio(11, 1));
+        assert_eq!(solution.values, vec![ratio(3, 1), ratio(1, 1)]);
+    }
+
+    #[test]
+    fn infeasible() {
+        let mut lp = LinearProgram::new(2);
+        lp.add_constraint(expr(&[(0, 1), (1, 1)]), Relation::LessEq, ratio(1, 1));
+        lp.add_constraint(expr(&[(0, 1), (1, 1)]), Relation::GreaterEq, ratio(2, 1));
+        assert_eq!(
+            lp.maximize(&expr(&[(0, 1)])).unwrap_err(),
+            LpError::Infeasible
+        );
+    }
+
+    #[test]
+    fn unbounded() {
+        let mut lp = LinearProgram::new(2);
+        lp.add_constraint(expr(&[(0, 1), (1, -1)]), Relation::LessEq, ratio(1, 1));
+        assert_eq!(
+            lp.maximize(&expr(&[(0, 1)])).unwrap_err(),
+            LpError::Unbounded
+        );
+    }
+
+    #[test]
+    fn negative_right_hand_sides() {
+        // x - y = -1 and -x - y >= -5, so y = x + 1 and x <= 2.
+        let mut lp = LinearProgram::new(2);
+        lp.add_constraint(expr(&[(0, 1), (1, -1)]), Relation::Eq, ratio(-1, 1));
+        lp.add_constraint(expr(&[(0, -1), (1, -1)]), Relation::GreaterEq, ratio(-5, 1));
+        let solution = lp.maximize(&expr(&[(0, 1)])).unwrap();
+        assert_eq!(solution.objective, ratio(2, 1));
+        assert_eq!(solution.values, vec![ratio(2, 1), ratio(3, 1)]);
+    }
+
+    #[test]
+    fn redundant_rows() {
+        // The second and third rows are multiples of the first.
+        let mut lp = LinearProgram::new(2);
+        lp.add_constraint(expr(&[(0, 1), (1, 1)]), Relation::Eq, ratio(2, 1));
+        lp.add_constraint(expr(&[(0, 2), (1, 2)]), Relation::Eq, ratio(4, 1));
+        lp.add_constraint(expr(&[(0, -1), (1, -1)]), Relation::Eq, ratio(-2, 1));
+        let solution = lp.maximize(&expr(&[(0, 1), (1, 2)])).unwrap();
+        assert_eq!(solution.objective, ratio(4, 1));
+        assert_eq!(solution.values, vec![ratio(0, 1), ratio(2, 1)]);
+    }
+
+    #[test]
+    fn degenerate() {
+        // Beale's example, which cycles forever under Dantzig's rule alone.
+        let mut lp = LinearProgram::new(4);
+        lp.add_constraint(
+            vec![
+                (0, ratio(1, 4)),
+                (1, ratio(-8, 1)),
+                (2, ratio(-1, 1)),
+                (3, ratio(9, 1)),
+            ],
+            Relation::LessEq,
+            ratio(0, 1),
+        );
+        lp.add_constraint(
+            vec![
+                (0, ratio(1, 2)),
+                (1, ratio(-12, 1)),
+                (2, ratio(-1, 2)),
+                (3, ratio(3, 1)),
+            ],
+            Relation::LessEq,
+            ratio(0, 1),
+        );
+        lp.add_constraint(expr(&[(2, 1)]), Relation::LessEq, ratio(1, 1));
+        let objective = vec![
+            (0, ratio(3, 4)),
+            (1, ratio(-20, 1)),
+            (2, ratio(1, 2)),
+            (3, ratio(-6, 1)),
+        ];
+        let solution = lp.maximize(&objective).unwrap();
+        assert_eq!(solution.objective, ratio(5, 4));
+        assert_eq!(
+            solution.values,
+            vec![ratio(1, 1), ratio(0, 1), ratio(1, 1), ratio(0, 1)]
+        );
+    }
+}
@@ -0,0 +1,293 @@
+//! Counterfactual regret minimization.
+
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+
+use crate::game::*;
+use crate::strategy::Strategy;
+use crate::table::*;
+
+/// Parameters of [Discounted CFR](https://arxiv.org/abs/1809.04040).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CFRDiscounting {
+    pub alpha: f64,
+    pub beta: f64,
+    pub gamma: f64,
+}
+
+/// Which flavour of CFR to run.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum CFRVariant {
+    /// Plain CFR with uniform averaging.
+    Vanilla,
+    /// Discounted CFR.
+    Discounted(CFRDiscounting),
+    /// [CFR+](https://arxiv.org/abs/1407.5042): cumulative regrets are floored
+    /// at zero and the average strategy weights iteration `t` by `t`.
+    Plus,
+}
+
+/// State of a CFR solve of a single round.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CFR {
+    pub variant: CFRVariant,
+    pub total_regrets: InfoStateRegrets,
+    pub average_strategy: Strategy,
+    pub t: usize,
+
+    // Intermediate values, for debugging.
+    pub expected_value: MetaStateTable,
+    pub counterfactual_probs: MetaStateTable,
+    pub metastate_regrets: MetaStateTable,
+    pub infostate_regrets: InfoStateRegrets,
+
+    pub player_to_update: Option<Player>,
+}
+impl CFR {
+    /// Start a new solve.  With `alternating_updates` only one player's
+    /// regrets are updated each round, starting with the first player.
+    pub fn new(variant: CFRVariant, alternating_updates: bool) -> CFR {
+        CFR {
+            variant,
+            total_regrets: InfoStateRegrets::empty(),
+            average_strategy: Strategy {
+                probs: ActionTable::default(),
+            },
+            t: 0,
+            expected_value: MetaStateTable::default(),
+            counterfactual_probs: MetaStateTable::default(),
+            metastate_regrets: MetaStateTable::default(),
+            infostate_regrets: InfoStateRegrets::empty(),
+            player_to_update: if alternating_updates {
+                Some(Player::Player1)
+            } else {
+                None
+            },
+        }
+    }
+
+    fn update_avg_strategy(&mut self, tree: &GameTree, strategy: &Strategy) {
+        if self.average_strategy.probs.is_empty() {
+            self.average_strategy.probs = ActionTable::new(tree, 0.0);
+        }
+        let t = self.t as f64;
+        let ratio = match &self.variant {
+            CFRVariant::Vanilla => t / (t + 1.0),
+            CFRVariant::Discounted(discount) => (t / (t + 1.0)).powf(discount.gamma),
+            CFRVariant::Plus => t / (t + 2.0),
+        };
+        for (infostate, avg_probs) in self.average_strategy.probs.iter_mut() {
+            if self
+                .player_to_update
+                .map(|p| p == tree.current_player[infostate.state])
+                .unwrap_or(true)
+            {
+                for (avg_prob, prob) in avg_probs.iter_mut().zip(&strategy.probs[&infostate]) {
+                    *avg_prob = ratio * *avg_prob + (1.0 - ratio) * prob;
+                }
+            }
+        }
+        if self
+            .player_to_update
+            .map(|p| p == Player::Player2)
+            .unwrap_or(true)
+        {
+            self.t += 1;
+        }
+    }
+
+    /// Run one iteration of CFR from `strategy` and return the strategy for
+    /// the next iteration.  The average strategy is updated as a side effect.
+    pub fn cfr_round(
+        &mut self,
+        strategy: &Strategy,
+        tree: &GameTree,
+        outcome_values: &OutcomeValues,
+    ) -> Strategy {
+        self.expected_value = strategy.expected_values(tree, outcome_values);
+        // for (s, value) in &ev {
+        //     println!("State has value {}:", value);
+        //     println!("Goals {:?} {:?}", s.p1goal, s.p2goal);
+        //     println!("{:?}", game_tree.states[s.state]);
+        // }
+        // println!("Expected values: {:?}", ev);
+
+        for p1goal in Outcome::iter() {
+            for p2goal in Outcome::iter() {
+                let ret = self.expected_value[&MetaState {
+                    state: 0,
+                    p1goal,
+                    p2goal,
+                }];
+                log::trace!(
+                    "EV for first player with goals {:?} {:?} {}",
+                    p1goal,
+                    p2goal,
+                    ret
+                );
+            }
+        }
+        log::debug!("Overall expected value {}", self.overall_ev(tree));
+
+        self.counterfactual_probs = strategy.counterfactual_probs(tree);
+        // for (s, prob) in &counterfactual_probs {
+        //     println!("State has CF prob {}:", prob);
+        //     println!("Goals {:?} {:?}", s.p1goal, s.p2goal);
+        //     println!("{:?}", game_tree.states[s.state]);
+        // }
+        self.metastate_regrets =
+            strategy.metastate_regrets(tree, &self.expected_value, &self.counterfactual_probs);
+        // for (s, regret) in &metastate_regrets {
+        //     println!("State has regret {}:", regret);
+        //     println!("Goals {:?} {:?}", s.p1goal, s.p2goal);
+        //     println!("{:?}", game_tree.states[s.state]);
+        // }
+
+        self.infostate_regrets =
+            InfoStateRegrets::from_metastate_regrets(&self.metastate_regrets, tree);
+        // for (s, regret) in &infostate_regrets.0 {
+        //     println!("State has regrets {:?}:", regret);
+        //     println!("nchildren {}", game_tree.children[s.state].len());
+        //     println!("Goals {:?}", s.goal);
+        //     println!("{:?}", game_tree.states[s.state]);
+        // }
+
+        if let CFRVariant::Discounted(discount) = &self.variant {
+            self.total_regrets
+                .discount(tree, self.player_to_update, discount, self.t);
+        }
+        self.infostate_regrets
+            .for_player(tree, self.player_to_update);
+        self.total_regrets.add(&self.infostate_regrets);
+        if let CFRVariant::Plus = self.variant {
+            self.total_regrets.floor();
+        }
+        let strategy = self.total_regrets.regret_matching_strategy(tree);
+        // for (s, prob) in &strategy.probs {
+        //     println!("State has probs {:?}:", prob);
+        //     println!("nchildren {}", game_tree.children[s.state].len());
+        //     println!("Goals {:?}", s.goal);
+        //     println!("{:?}", game_tree.states[s.state]);
+        // }
+
+        self.update_avg_strategy(tree, &strategy);
+
+        self.player_to_update = self.player_to_update.map(|p| p.opponent());
+        strategy
+    }
+
+    /// The first player's expected value under the strategy passed to the last
+    /// `cfr_round`, averaged over how `tree` deals the goals.
+    pub fn overall_ev(&self, tree: &GameTree) -> f64 {
+        self.expected_value.root_value(tree)
+    }
+}
+
+/// Per-action regrets of every information state.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct InfoStateRegrets(pub ActionTable);
+
+impl InfoStateRegrets {
+    pub fn empty() -> Self {
+        Self(ActionTable::default())
+    }
+    pub fn from_metastate_regrets(
+        metastate_regrets: &MetaStateTable,
+        tree: &GameTree,
+    ) -> InfoStateRegrets {
+        let mut result = ActionTable::new(tree, 0.0);
+        for (infostate, regret) in result.iter_mut() {
+            let id = infostate.state;
+            for (action_regret, &child) in regret.iter_mut().zip(&tree.children[id]) {
+                for other_goal in Outcome::iter() {
+                    let (p1goal, p2goal) = if tree.current_player[id] == Player::Player1 {
+                        (infostate.goal, other_goal)
+                    } else {
+                        (other_goal, infostate.goal)
+                    };
+                    let child_metastate = MetaState {
+                        state: child,
+                        p1goal,
+                        p2goal,
+                    };
+                    *action_regret += metastate_regrets[&child_metastate];
+                }
+            }
+        }
+        InfoStateRegrets(result)
+    }
+
+    pub fn discount(
+        &mut self,
+        game_tree: &GameTree,
+        player: Option<Player>,
+        discount: &CFRDiscounting,
+        t: usize,
+    ) {
+        let positive_exp = ((t + 1) as f64).powf(discount.alpha);
+        let negative_exp = ((t + 1) as f64).powf(discount.beta);
+        for (infostate, regrets) in self.0.iter_mut() {
+            if player
+                .map(|p| p == game_tree.current_player[infostate.state])
+                .unwrap_or(true)
+            {
+                for regret in regrets {
+                    if *regret >= 0.0 {
+                        *regret *= positive_exp / (positive_exp + 1.0);
+                    } else {
+                        *regret *= negative_exp / (negative_exp + 1.0);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn add(&mut self, other: &InfoStateRegrets) {
+        if self.0.is_empty() {
+            self.0 = ActionTable::from_offsets(other.0.offsets().to_vec(), 0.0);
+        }
+        for (regret, other_regret) in self.0.values_mut().iter_mut().zip(other.0.values()) {
+            *regret += other_regret;
+        }
+    }
+
+    /// Clamp negative regrets to zero, as in CFR+.
+    pub fn floor(&mut self) {
+        for regret in self.0.values_mut() {
+            *regret = f64::max(*regret, 0.0);
+        }
+    }
+
+    pub fn for_player(&mut self, tree: &GameTree, player: Option<Player>) {
+        for (infostate, regrets) in self.0.iter_mut() {
+            if player
+                .map(|p| p != tree.current_player[infostate.state])
+                .unwrap_or(false)
+            {
+                regrets.fill(0.0);
+            }
+        }
+    }
+
+    pub fn regret_matching_strategy(&self, tree: &GameTree) -> Strategy {
+        let mut result = ActionTable::new(tree, 0.0);
+        for (infostate, probs) in result.iter_mut() {
+            let regrets = &self.0[&infostate];
+
+            let mut positive_regret = 0f64;
+            for regret in regrets {
+                positive_regret += f64::max(*regret, 0f64);
+            }
+
+            if positive_regret > 0.0 {
+                for (prob, regret) in probs.iter_mut().zip(regrets) {
+                    *prob = f64::max(*regret, 0.0) / positive_regret;
+                }
+            } else {
+                let nchildren = probs.len();
+                probs.fill(1.0 / nchildren as f64);
+            }
+        }
+        Strategy { probs: result }
+    }
+}
@@ -0,0 +1,375 @@
+//! Behaviour strategies over information states.
+
+use rayon::prelude::*;
+use serde::ser::{SerializeMap, SerializeStruct};
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::HashMap;
+use strum::IntoEnumIterator;
+
+use crate::game::*;
+use crate::table::*;
+
+/// Action probabilities for every information state, in the order of
+/// `GameTree::children`.
+///
+/// On disk this is still written as a map from [`InfoState`] to
+/// probabilities, so strategy files from older versions remain readable.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(try_from = "StrategyFile")]
+pub struct Strategy {
+    pub probs: ActionTable,
+}
+
+impl Strategy {
+    pub fn uniform(tree: &GameTree) -> Strategy {
+        let mut probs = ActionTable::new(tree, 0.0);
+        for (_, probs) in probs.iter_mut() {
+            let nchildren = probs.len();
+            probs.fill(1.0 / nchildren as f64);
+        }
+        Strategy { probs }
+    }
+
+    /// The value of every metastate to the first player when both players
+    /// follow this strategy.
+    pub fn expected_values(
+        &self,
+        tree: &GameTree,
+        outcome_values: &OutcomeValues,
+    ) -> MetaStateTable {
+        let mut result = MetaStateTable::new(tree, 0.0);
+        result
+            .par_goal_pairs_mut()
+            .for_each(|((p1goal, p2goal), values)| {
+                for i in (0..tree.states.len()).rev() {
+                    let metastate = MetaState {
+                        state: i,
+                        p1goal,
+                        p2goal,
+                    };
+                    if let Some(outcomes) = metastate.outcomes(tree) {
+                        values[i] = outcome_values.evaluate(&metastate, tree, outcomes);
+                    } else {
+                        let infostate = metastate.info_state(tree);
+                        let mut sum = 0f64;
+                        let mut count = 0f64;
+                        for (p, &child) in
+                            itertools::zip(self.probs[&infostate].iter(), tree.children[i].iter())
+                        {
+                            sum += values[child] * p;
+                            count += p;
+                        }
+                        values[i] = sum / count;
+                    }
+                }
+            });
+        result
+    }
+
+    /// For every metastate, the probability of reaching it counting only chance
+    /// and the moves of the player who is *not* to move there.
+    pub fn counterfactual_probs(&self, tree: &GameTree) -> MetaStateTable {
+        // Reach probabilities due to the first player's moves and due to the
+        // second player's moves, respectively.
+        let mut counterfactual_probs2 = MetaStateTable::new(tree, 0.0);
+        let mut counterfactual_probs1 = MetaStateTable::new(tree, 0.0);
+        counterfactual_probs1
+            .par_goal_pairs_mut()
+            .zip(counterfactual_probs2.par_goal_pairs_mut())
+            .for_each(|(((p1goal, p2goal), probs1), (_, probs2))| {
+                probs1[0] = tree.goals.prob(p1goal, p2goal);
+                probs2[0] = tree.goals.prob(p1goal, p2goal);
+                for id in 0..tree.states.len() {
+                    let metastate = MetaState {
+                        state: id,
+                        p1goal,
+                        p2goal,
+                    };
+                    let info_state = metastate.info_state(tree);
+                    let (active_probs, passive_probs) = match tree.current_player[id] {
+                        Player::Player1 => (&mut *probs2, &mut *probs1),
+                        Player::Player2 => (&mut *probs1, &mut *probs2),
+                    };
+                    let (active_prob, passive_prob) = (active_probs[id], passive_probs[id]);
+                    for (prob, &child) in
+                        itertools::zip(self.probs[&info_state].iter(), tree.children[id].iter())
+                    {
+                        active_probs[child] = active_prob * prob;
+                        passive_probs[child] = passive_prob;
+                    }
+                }
+                // Reuse counterfactual_probs1 to combine the two tables.
+                for id in 0..tree.states.len() {
+                    if tree.current_player[id] == Player::Player2 {
+                        probs1[id] = probs2[id];
+                    }
+                }
+            });
+        counterfactual_probs1
+    }
+
+    /// The instantaneous regret of each move, stored at the metastate the move
+    /// leads to, from the point of view of the player who made it.
+    pub fn metastate_regrets(
+        &self,
+        tree: &GameTree,
+        expected_value: &MetaStateTable,
+        counterfactual_probs: &MetaStateTable,
+    ) -> MetaStateTable {
+        let mut result = MetaStateTable::new(tree, 0.0);
+        result
+            .par_goal_pairs_mut()
+            .for_each(|((p1goal, p2goal), regrets)| {
+                let expected_value = expected_value.goal_pair(p1goal, p2goal);
+                let counterfactual_probs = counterfactual_probs.goal_pair(p1goal, p2goal);
+                for id in 0..tree.states.len() {
+                    let counterfactual_value = expected_value[id] * counterfactual_probs[id];
+
+                    for &child in tree.children[id].iter() {
+                        let regret =
+                            expected_value[child] * counterfactual_probs[id] - counterfactual_value;
+
+                        regrets[child] = if tree.current_player[id] == Player::Player1 {
+                            regret
+                        } else {
+                            -regret
+                        };
+                    }
+                }
+            });
+        result
+    }
+
+    /// Play `player1_strategy` as the first player and `player2_strategy` as
+    /// the second.
+    pub fn splice(
+        player1_strategy: &Strategy,
+        player2_strategy: &Strategy,
+        tree: &GameTree,
+    ) -> Strategy {
+        let mut result = player1_strategy.clone();
+        for (infostate, probs) in result.probs.iter_mut() {
+            if tree.current_player[infostate.state] == Player::Player2 {
+                probs.copy_from_slice(&player2_strategy.probs[&infostate]);
+            }
+        }
+        result
+    }
+
+    /// The largest change in any action probability between two strategies.
+    pub fn max_difference(&self, other: &Strategy) -> f64 {
+        let mut max = 0.0;
+        for (v, other_v) in itertools::zip(self.probs.values(), other.probs.values()) {
+            max = f64::max(f64::abs(v - other_v), max);
+        }
+        max
+    }
+
+    /// The probability of reaching every metastate, goals included.
+    pub fn visit_probs(&self, tree: &GameTree) -> MetaStateTable {
+        let mut result = MetaStateTable::new(tree, 0.0);
+        result
+            .par_goal_pairs_mut()
+            .for_each(|((p1goal, p2goal), probs)| {
+                probs[0] = tree.goals.prob(p1goal, p2goal);
+                for id in 0..tree.states.len() {
+                    let info_state = MetaState {
+                        state: id,
+                        p1goal,
+                        p2goal,
+                    }
+                    .info_state(tree);
+                    let prob = probs[id];
+                    for (child_prob, &child) in
+                        itertools::zip(self.probs[&info_state].iter(), tree.children[id].iter())
+                    {
+                        probs[child] = prob * child_prob;
+                    }
+                }
+            });
+        result
+    }
+
+    /// The probability of each way a round can end when both players follow
+    /// this strategy, keyed by whether the first and second player scored.
+    pub fn outcome_probs(&self, tree: &GameTree) -> HashMap<(bool, bool), f64> {
+        let visit_probs = self.visit_probs(tree);
+        let mut result = HashMap::new();
+        for (state, outcome) in tree.terminals.iter().enumerate() {
+            let outcome = match outcome {
+                Some(outcome) => *outcome,
+                None => continue,
+            };
+            for p1goal in Outcome::iter() {
+                for p2goal in Outcome::iter() {
+                    let metastate = MetaState {
+                        state,
+                        p1goal,
+                        p2goal,
+                    };
+                    let visit_prob = visit_probs[&metastate];
+                    let (p1scored, p2scored) = (outcome == p1goal, outcome.reverse() == p2goal);
+                    *result.entry((p1scored, p2scored)).or_insert(0.0) += visit_prob;
+                }
+            }
+        }
+        result
+    }
+
+    /// What `observer` should believe about the other player's goal at
+    /// `metastate`, knowing their own goal, the goal distribution and the
+    /// moves played so far, if the other player follows this strategy.
+    /// Returns `None` if this strategy never makes the other player's moves
+    /// so far.
+    pub fn goal_beliefs(
+        &self,
+        tree: &GameTree,
+        metastate: &MetaState,
+        observer: Player,
+    ) -> Option<GoalBeliefs> {
+        let mut probs = [0.0; 3];
+        for goal in Outcome::iter() {
+            let (p1goal, p2goal) = match observer {
+                Player::Player1 => (metastate.p1goal, goal),
+                Player::Player2 => (goal, metastate.p2goal),
+            };
+            // The observer's own moves are equally likely whatever the other
+            // goal is, so only the other player's moves matter.
+            let mut prob = tree.goals.prob(p1goal, p2goal);
+            let mut state = metastate.state;
+            while let Some(parent) = tree.parents[state] {
+                if tree.current_player[parent] != observer {
+                    let index = tree.children[parent]
+                        .iter()
+                        .position(|&child| child == state)
+                        .unwrap();
+                    prob *= self.probs[&InfoState {
+                        state: parent,
+                        goal,
+                    }][index];
+                }
+                state = parent;
+            }
+            probs[goal.index()] = prob;
+        }
+        let total: f64 = probs.iter().sum();
+        if total <= 0.0 {
+            return None;
+        }
+        Some(GoalBeliefs {
+            probs: probs.map(|p| p / total),
+        })
+    }
+}
+
+/// A probability for each goal, indexed by [`Outcome::index`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GoalBeliefs {
+    pub probs: [f64; 3],
+}
+
+impl GoalBeliefs {
+    pub fn prob(&self, goal: Outcome) -> f64 {
+        self.probs[goal.index()]
+    }
+}
+
+/// Written like "Win 62% / Tie 30% / Lose 8%", most likely goal first.
+impl std::fmt::Display for GoalBeliefs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut goals: Vec<Outcome> = Outcome::iter().collect();
+        goals.sort_by(|a, b| self.prob(*b).total_cmp(&self.prob(*a)));
+        for (i, goal) in goals.into_iter().enumerate() {
+            if i > 0 {
+                f.write_str(" / ")?;
+            }
+            write!(f, "{} {:.0}%", goal, 100.0 * self.prob(goal))?;
+        }
+        Ok(())
+    }
+}
+
+/// The on-disk form of a [`Strategy`].
+#[derive(Deserialize)]
+struct StrategyFile {
+    probs: HashMap<InfoState, Vec<f64>>,
+}
+
+/// Fails if the goals of a state have different numbers of moves, as in a
+/// truncated or edited file.
+impl TryFrom<StrategyFile> for Strategy {
+    type Error = String;
+
+    fn try_from(file: StrategyFile) -> Result<Strategy, String> {
+        let num_states = file.probs.keys().map(|i| i.state + 1).max().unwrap_or(0);
+        let mut offsets = vec![0];
+        for state in 0..num_states {
+            let nchildren = Outcome::iter()
+                .find_map(|goal| file.probs.get(&InfoState { state, goal }))
+                .map_or(0, |probs| probs.len());
+            offsets.push(offsets[state] + nchildren);
+        }
+        let mut probs = ActionTable::from_offsets(offsets, 0.0);
+        for (infostate, probs) in probs.iter_mut() {
+            if let Some(file_probs) = file.probs.get(&infostate) {
+                if file_probs.len() != probs.len() {
+                    return Err(format!(
+                        "state {} has {} moves with goal {} but {} with another goal",
+                        infostate.state,
+                        file_probs.len(),
+                        infostate.goal,
+                        probs.len()
+                    ));
+                }
+                probs.copy_from_slice(file_probs);
+            }
+        }
+        Ok(Strategy { probs })
+    }
+}
+
+impl Serialize for Strategy {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        struct Probs<'a>(&'a ActionTable);
+        impl Serialize for Probs<'_> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let mut map = serializer.serialize_map(Some(self.0.num_states() * 3))?;
+                for (infostate, probs) in self.0.iter() {
+                    map.serialize_entry(&infostate, probs)?;
+                }
+                map.end()
+            }
+        }
+        let mut result = serializer.serialize_struct("Strategy", 1)?;
+        result.serialize_field("probs", &Probs(&self.probs))?;
+        result.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct File {
+        probs: HashMap<InfoState, Vec<f64>>,
+    }
+
+    fn infostate(state: StateId, goal: Outcome) -> InfoState {
+        InfoState { state, goal }
+    }
+
+    #[test]
+    fn load_from_file_form() {
+        let tree = GameTree::with_board("2x2:2".parse().unwrap());
+        let strategy = Strategy::uniform(&tree);
+        let bytes = bincode::serialize(&strategy).unwrap();
+        assert_eq!(bincode::deserialize::<Strategy>(&bytes).unwrap(), strategy);
+
+        let mut probs = HashMap::new();
+        probs.insert(infostate(0, Outcome::Win), vec![0.5, 0.5]);
+        probs.insert(infostate(0, Outcome::Lose), vec![1.0]);
+        let bytes = bincode::serialize(&File { probs }).unwrap();
+        assert!(bincode::deserialize::<Strategy>(&bytes).is_err());
+    }
+}
@@ -0,0 +1,240 @@
+//! Flat, index-based storage for values attached to game states.
+//!
+//! Every table is laid out goal-major: all states for the first goal (or goal
+//! pair), then all states for the next one, and so on.  Per-action tables use
+//! the action offsets of the [`GameTree`] so the actions of one information
+//! state are a contiguous slice.
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::ops::{Index, IndexMut};
+use strum::IntoEnumIterator;
+
+use crate::game::*;
+
+/// Number of (p1goal, p2goal) pairs.
+pub const NUM_GOAL_PAIRS: usize = 9;
+
+/// Position of a goal pair in goal-major tables, in `Outcome::iter()` order.
+pub fn goal_pair_index(p1goal: Outcome, p2goal: Outcome) -> usize {
+    p1goal.index() * 3 + p2goal.index()
+}
+
+/// The goal pair at `index`, the inverse of [`goal_pair_index`].
+pub fn goal_pair_from_index(index: usize) -> (Outcome, Outcome) {
+    (
+        Outcome::iter().nth(index / 3).unwrap(),
+        Outcome::iter().nth(index % 3).unwrap(),
+    )
+}
+
+/// One value for every [`MetaState`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct MetaStateTable {
+    num_states: usize,
+    values: Vec<f64>,
+}
+
+impl MetaStateTable {
+    pub fn new(tree: &GameTree, value: f64) -> MetaStateTable {
+        MetaStateTable {
+            num_states: tree.states.len(),
+            values: vec![value; tree.states.len() * NUM_GOAL_PAIRS],
+        }
+    }
+
+    fn index_of(&self, metastate: &MetaState) -> usize {
+        goal_pair_index(metastate.p1goal, metastate.p2goal) * self.num_states + metastate.state
+    }
+
+    /// `None` if the table was never filled in, e.g. a solver state loaded
+    /// before any iteration ran.
+    pub fn get(&self, metastate: &MetaState) -> Option<f64> {
+        self.values.get(self.index_of(metastate)).copied()
+    }
+
+    /// The values of every state for one goal pair.
+    pub fn goal_pair(&self, p1goal: Outcome, p2goal: Outcome) -> &[f64] {
+        let start = goal_pair_index(p1goal, p2goal) * self.num_states;
+        &self.values[start..start + self.num_states]
+    }
+
+    /// The value at the start of the round, averaged over how `tree` deals
+    /// the goals.
+    pub fn root_value(&self, tree: &GameTree) -> f64 {
+        tree.goals
+            .expectation(|p1goal, p2goal| self.goal_pair(p1goal, p2goal)[0])
+    }
+
+    /// The values of every state for each goal pair, to be filled in in
+    /// parallel.  Each slice is indexed by [`StateId`].
+    pub fn par_goal_pairs_mut(
+        &mut self,
+    ) -> impl IndexedParallelIterator<Item = ((Outcome, Outcome), &mut [f64])> {
+        self.values
+            .par_chunks_mut(self.num_states)
+            .enumerate()
+            .map(|(index, values)| (goal_pair_from_index(index), values))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+impl Index<&MetaState> for MetaStateTable {
+    type Output = f64;
+    fn index(&self, metastate: &MetaState) -> &f64 {
+        &self.values[self.index_of(metastate)]
+    }
+}
+
+impl IndexMut<&MetaState> for MetaStateTable {
+    fn index_mut(&mut self, metastate: &MetaState) -> &mut f64 {
+        let index = self.index_of(metastate);
+        &mut self.values[index]
+    }
+}
+
+/// One value for every [`InfoState`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct InfoStateTable {
+    num_states: usize,
+    values: Vec<f64>,
+}
+
+impl InfoStateTable {
+    pub fn new(tree: &GameTree, value: f64) -> InfoStateTable {
+        InfoStateTable {
+            num_states: tree.states.len(),
+            values: vec![value; tree.states.len() * 3],
+        }
+    }
+
+    fn index_of(&self, infostate: &InfoState) -> usize {
+        infostate.goal.index() * self.num_states + infostate.state
+    }
+
+    pub fn get(&self, infostate: &InfoState) -> Option<f64> {
+        self.values.get(self.index_of(infostate)).copied()
+    }
+
+    /// The values of every state for one goal, indexed by [`StateId`].
+    pub fn goal_mut(&mut self, goal: Outcome) -> &mut [f64] {
+        let start = goal.index() * self.num_states;
+        &mut self.values[start..start + self.num_states]
+    }
+}
+
+impl Index<&InfoState> for InfoStateTable {
+    type Output = f64;
+    fn index(&self, infostate: &InfoState) -> &f64 {
+        &self.values[self.index_of(infostate)]
+    }
+}
+
+impl IndexMut<&InfoState> for InfoStateTable {
+    fn index_mut(&mut self, infostate: &InfoState) -> &mut f64 {
+        let index = self.index_of(infostate);
+        &mut self.values[index]
+    }
+}
+
+/// One value per action of every [`InfoState`], in the order of
+/// `GameTree::children`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ActionTable {
+    /// The actions of state `s` are `offsets[s]..offsets[s + 1]` within
+    /// each goal's block.
+    offsets: Vec<usize>,
+    values: Vec<f64>,
+}
+
+impl ActionTable {
+    pub fn new(tree: &GameTree, value: f64) -> ActionTable {
+        ActionTable::from_offsets(tree.action_offsets.clone(), value)
+    }
+
+    /// A table with the given per-state action offsets, which must start at 0
+    /// and have one more entry than there are states.
+    pub fn from_offsets(offsets: Vec<usize>, value: f64) -> ActionTable {
+        let num_actions = *offsets.last().unwrap_or(&0);
+        ActionTable {
+            offsets,
+            values: vec![value; num_actions * 3],
+        }
+    }
+
+    pub fn num_states(&self) -> usize {
+        self.offsets.len().saturating_sub(1)
+    }
+
+    pub fn offsets(&self) -> &[usize] {
+        &self.offsets
+    }
+
+    fn num_actions(&self) -> usize {
+        *self.offsets.last().unwrap_or(&0)
+    }
+
+    fn range_of(&self, infostate: &InfoState) -> std::ops::Range<usize> {
+        let base = infostate.goal.index() * self.num_actions();
+        base + self.offsets[infostate.state]..base + self.offsets[infostate.state + 1]
+    }
+
+    /// `None` if the table has no entry for `infostate`, e.g. because it was
+    /// never filled in.
+    pub fn get(&self, infostate: &InfoState) -> Option<&[f64]> {
+        if infostate.state >= self.num_states() {
+            return None;
+        }
+        Some(&self[infostate])
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+
+    /// All values of all information states, goal-major.
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+
+    pub fn values_mut(&mut self) -> &mut [f64] {
+        &mut self.values
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (InfoState, &[f64])> {
+        Outcome::iter()
+            .flat_map(|goal| (0..self.num_states()).map(move |state| InfoState { state, goal }))
+            .map(|infostate| (infostate, &self[&infostate]))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (InfoState, &mut [f64])> {
+        let num_states = self.num_states();
+        let offsets = &self.offsets;
+        let mut rest: &mut [f64] = &mut self.values;
+        Outcome::iter()
+            .flat_map(move |goal| (0..num_states).map(move |state| InfoState { state, goal }))
+            .map(move |infostate| {
+                let len = offsets[infostate.state + 1] - offsets[infostate.state];
+                let (head, tail) = std::mem::take(&mut rest).split_at_mut(len);
+                rest = tail;
+                (infostate, head)
+            })
+    }
+}
+
+impl Index<&InfoState> for ActionTable {
+    type Output = [f64];
+    fn index(&self, infostate: &InfoState) -> &[f64] {
+        &self.values[self.range_of(infostate)]
+    }
+}
+
+impl IndexMut<&InfoState> for ActionTable {
+    fn index_mut(&mut self, infostate: &InfoState) -> &mut [f64] {
+        let range = self.range_of(infostate);
+        &mut self.values[range]
+    }
+}
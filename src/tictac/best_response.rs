@@ -0,0 +1,53 @@
+use tictac::cli::*;
+use tictac::*;
+
+use crate::or_exit;
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[clap(flatten)]
+    solution: SolutionArgs,
+
+    #[clap(flatten)]
+    threads: ThreadArgs,
+
+    #[clap(flatten)]
+    tree: TreeArgs,
+}
+
+pub fn run(args: Args) {
+    args.threads.init();
+    let game_tree = args.tree.build();
+
+    let solution = or_exit(args.solution.load(&game_tree), "couldn't load solution");
+    let (base_strategy, _) = solution.bot_strategy(args.solution.average_strategy);
+    let counterfactual_probs = base_strategy.counterfactual_probs(&game_tree);
+    let best_response = BestResponse::new(
+        base_strategy,
+        &game_tree,
+        &counterfactual_probs,
+        &OutcomeValues::default(),
+    );
+
+    io::save(
+        &io::best_response_path(&args.solution.solutions_dir, solution.iteration),
+        &best_response,
+    )
+    .unwrap();
+
+    let p1_exploiter = Strategy::splice(base_strategy, &best_response.strategy, &game_tree);
+
+    for (s, prob) in p1_exploiter.probs.iter() {
+        log::trace!("State {} has probs {:?}:", s.state, prob);
+        log::trace!("Goals {:?}", s.goal);
+        log::trace!("{:?}", game_tree.states[s.state]);
+    }
+    let report = ExploitabilityReport::with_best_response(
+        &game_tree,
+        base_strategy,
+        &best_response,
+        &counterfactual_probs,
+        &OutcomeValues::default(),
+    );
+    println!("{}", report);
+}
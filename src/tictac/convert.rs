@@ -0,0 +1,78 @@
+use clap::ValueEnum;
+use clap::ValueHint;
+use std::path::{Path, PathBuf};
+
+use tictac::portable::{self, Portable};
+use tictac::*;
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Debug)]
+enum Kind {
+    Strategy,
+    BestResponse,
+    Cfr,
+}
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[clap(parse(from_os_str), value_hint = ValueHint::FilePath)]
+    input: PathBuf,
+
+    #[clap(parse(from_os_str), value_hint = ValueHint::FilePath)]
+    output: PathBuf,
+
+    /// What the input holds.  Defaults to what its file name says:
+    /// strategy_N and average_strategy_N are strategies, best_response_N
+    /// best responses and debug_N solver states.
+    #[clap(long, value_enum)]
+    kind: Option<Kind>,
+
+    /// The board the artifact was solved on, as ROWSxCOLS or ROWSxCOLS:K for
+    /// K in a row.
+    #[clap(long, default_value = "3x3")]
+    board: Board,
+}
+
+fn kind_of_path(path: &Path) -> Option<Kind> {
+    let name = path.file_stem()?.to_str()?;
+    let prefix = name.trim_end_matches(|c: char| c.is_ascii_digit());
+    match prefix {
+        "strategy_" | "average_strategy_" => Some(Kind::Strategy),
+        "best_response_" => Some(Kind::BestResponse),
+        "debug_" => Some(Kind::Cfr),
+        _ => None,
+    }
+}
+
+fn convert<T: Portable>(args: &Args, tree: &GameTree) -> Result<(), String> {
+    let value: T = portable::load(&args.input, tree)?;
+    portable::save(&args.output, &value, tree)
+}
+
+pub fn run(args: Args) {
+    let kind = args
+        .kind
+        .or_else(|| kind_of_path(&args.input))
+        .unwrap_or_else(|| {
+            eprintln!(
+                "can't tell what {} holds from its name, pass --kind",
+                args.input.display()
+            );
+            std::process::exit(1);
+        });
+    let game_tree = GameTree::with_board(args.board);
+
+    let result = match kind {
+        Kind::Strategy => convert::<Strategy>(&args, &game_tree),
+        Kind::BestResponse => convert::<BestResponse>(&args, &game_tree),
+        Kind::Cfr => convert::<CFR>(&args, &game_tree),
+    };
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+    log::info!(
+        "Converted {} to {}",
+        args.input.display(),
+        args.output.display()
+    );
+}
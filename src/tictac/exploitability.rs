@@ -0,0 +1,57 @@
+use clap::ValueHint;
+use std::collections::HashMap;
+
+use tictac::cli::*;
+use tictac::*;
+
+use crate::or_exit;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// The output directory of `tictac solve-match`.
+    #[clap(short, long, parse(from_os_str), value_hint = ValueHint::DirPath)]
+    solutions_dir: std::path::PathBuf,
+
+    #[clap(flatten)]
+    game: MatchArgs,
+
+    #[clap(flatten)]
+    threads: ThreadArgs,
+
+    #[clap(flatten)]
+    tree: TreeArgs,
+}
+
+pub fn run(args: Args) {
+    args.threads.init();
+    let game_tree = args.tree.build();
+    let format = args.game.format.match_format();
+
+    let bot = or_exit(
+        args.game.load_bot(&game_tree, &args.solutions_dir),
+        "couldn't load solutions",
+    );
+    let strategies = bot
+        .strategies
+        .iter()
+        .map(|(subgame, strategy)| (subgame.clone(), strategy))
+        .collect();
+
+    let bounds = match_exploitability(&game_tree, &format, &strategies);
+    log::info!("Found TOTAL exploitability of every subgame!");
+    print_bounds(&bounds, &format);
+}
+
+/// Print the bounds of every subgame, from the last rounds to the first.
+pub fn print_bounds(bounds: &HashMap<Subgame, SubgameBounds>, format: &MatchFormat) {
+    for subgame in format.solve_order().into_iter().flatten().flatten() {
+        let subgame_bounds = &bounds[&subgame];
+        println!(
+            "{} Max value {} Min Value {} Exploitability {}",
+            subgame,
+            subgame_bounds.max_value,
+            subgame_bounds.min_value,
+            subgame_bounds.exploitability()
+        );
+    }
+}
@@ -0,0 +1,187 @@
+use clap::ValueHint;
+use std::io::Write;
+
+use tictac::cli::*;
+use tictac::*;
+
+use crate::or_exit;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// The solution directory, the output of `tictac solve`.
+    #[clap(short, long, parse(from_os_str), value_hint = ValueHint::DirPath)]
+    solutions_dir: std::path::PathBuf,
+
+    #[clap(flatten)]
+    tree: TreeArgs,
+}
+
+fn best_response(args: &Args, i: usize) -> BestResponse {
+    let mut result = BestResponse::default();
+    let path = io::best_response_path(&args.solutions_dir, i);
+    if path.exists() {
+        result = io::load(&path).unwrap();
+    }
+    result
+}
+fn load_iteration(
+    args: &Args,
+    tree: &GameTree,
+    i: usize,
+) -> bincode::Result<(io::Solution, BestResponse)> {
+    let solution = io::load_solution(&args.solutions_dir, i, tree)?;
+    Ok((solution, best_response(args, i)))
+}
+
+fn char_to_outcome(c: char) -> Option<Outcome> {
+    match c {
+        'w' => Some(Outcome::Win),
+        't' => Some(Outcome::Tie),
+        'l' => Some(Outcome::Lose),
+        _ => None,
+    }
+}
+
+pub fn run(args: Args) {
+    let game_tree = args.tree.build();
+
+    let iteration = or_exit(
+        io::latest_iteration(&args.solutions_dir),
+        "couldn't read solution directory",
+    )
+    .unwrap_or_else(|| {
+        eprintln!("no strategy files in {}", args.solutions_dir.display());
+        std::process::exit(1);
+    });
+
+    let (mut solution, mut best_response) = or_exit(
+        load_iteration(&args, &game_tree, iteration),
+        "couldn't load solution",
+    );
+
+    let mut metastate = MetaState {
+        state: 0,
+        p1goal: Outcome::Win,
+        p2goal: Outcome::Win,
+    };
+
+    loop {
+        println!("Iteration {}", solution.iteration);
+        println!(
+            "P1 Goal: {} P2 Goal: {}",
+            metastate.p1goal, metastate.p2goal
+        );
+        println!("{:?}", game_tree.states[metastate.state]);
+        let infostate = metastate.info_state(&game_tree);
+        if let Some(cfr) = &solution.cfr {
+            println!(
+                "EV: {} CF Prob {} Parent CF Prob {:?}",
+                cfr.expected_value[&metastate],
+                cfr.counterfactual_probs[&metastate],
+                metastate
+                    .parent(&game_tree)
+                    .map(|m| cfr.counterfactual_probs[&m])
+            );
+            print!("Regrets: [");
+            for child in metastate.children(&game_tree) {
+                print!("{:1.4}, ", cfr.metastate_regrets[&child]);
+            }
+            println!("]\n");
+
+            println!(
+                "Infostate regrets {:?}",
+                &cfr.infostate_regrets.0[&infostate]
+            );
+            println!("Total regrets {:?}", &cfr.total_regrets.0[&infostate]);
+        } else {
+            println!(
+                "No solver state saved for this iteration, so EVs and regrets are unavailable.\n"
+            );
+        }
+        println!(
+            "Current strategy {:?}",
+            &solution.strategy.probs[&infostate]
+        );
+        match solution.average_strategy() {
+            Some((average_strategy, _)) => {
+                println!("Average strategy {:?}", &average_strategy.probs[&infostate])
+            }
+            None => println!("Average strategy not saved for this iteration"),
+        }
+        let (belief_strategy, _) = solution.bot_strategy(true);
+        for (observer, name, other) in
+            [(Player::Player1, "P1", "P2"), (Player::Player2, "P2", "P1")]
+        {
+            match belief_strategy.goal_beliefs(&game_tree, &metastate, observer) {
+                Some(beliefs) => println!("{} believes {} wants {}", name, other, beliefs),
+                None => println!("{} never expected {}'s moves", name, other),
+            }
+        }
+        println!(
+            "Best response value for P1 {:?} P2 {:?}",
+            best_response.p1_value.get(&InfoState {
+                state: metastate.state,
+                goal: metastate.p1goal
+            }),
+            best_response.p2_value.get(&InfoState {
+                state: metastate.state,
+                goal: metastate.p2goal
+            }),
+        );
+        println!(
+            "Best response strategy: {:?}",
+            best_response.strategy.probs.get(&infostate).unwrap_or(&[])
+        );
+
+        print!("> ");
+        std::io::stdout().flush().unwrap();
+        let mut line = String::new();
+        std::io::stdin().read_line(&mut line).unwrap();
+        match line.chars().next().unwrap_or(' ') {
+            'q' => break,
+            'i' => {
+                match line[1..].trim().parse() {
+                    Ok(i) => match load_iteration(&args, &game_tree, i) {
+                        Ok(loaded) => (solution, best_response) = loaded,
+                        Err(e) => println!("couldn't load iteration {}: {}", i, e),
+                    },
+                    Err(e) => println!("invalid iteration {}", e)
+                };
+            }
+            'm' => {
+                if let Ok(position)  = line[1..].trim().parse::<usize>(){
+                    if !(1..=game_tree.board.num_cells()).contains(&position) {
+                        println!("Bad position {}", position);
+                        continue;
+                    }
+                    let mut child_state = game_tree.states[metastate.state];
+                    child_state.moves[position-1]=child_state.moves.iter().max().unwrap()+1;
+                    let mut found = false;
+                    for symmetric_child in game_tree.children[metastate.state].iter() {
+                        if child_state.drop_history().is_symmetry(&game_tree.states[*symmetric_child].drop_history()) {
+                            metastate.state = *symmetric_child;
+                            found = true;
+                        }
+                    }
+                    if !found {
+                        println!("Invalid move!");
+                    }
+                } else {
+                    println!("Cannot parse move.");
+                }
+            }
+            'u' => {
+                metastate.state = game_tree.parents[metastate.state].unwrap_or(0);
+            }
+            'g' => {
+                let p1goal = line.chars().nth(2).and_then(char_to_outcome);
+                let p2goal = line.chars().nth(4).and_then(char_to_outcome);
+                match (p1goal, p2goal) {
+                     (Some(p1), Some(p2)) => {metastate.p1goal = p1; metastate.p2goal = p2}
+                    _ => {println!("Invalid arguments.  Must be like 'g l t'");}
+                }
+            }
+            _ => println!("Unrecognized command.  Valid commands are q (quit) i 3 (jump to iteration 3) m 5 (move at position 5), u (undo move), and g w l (set goals to P1 win, P2 lose)"),
+        }
+    }
+}
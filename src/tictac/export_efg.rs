@@ -0,0 +1,55 @@
+use clap::ValueHint;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use tictac::cli::*;
+use tictac::*;
+
+use crate::or_exit;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// The path of the .efg file to write.
+    #[clap(short, long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    output: std::path::PathBuf,
+
+    /// Export a round of a match at these scores, written as
+    /// P1SCORE,P2SCORE, or P1SCORE,P2SCORE,ROUND for a match played over a
+    /// fixed number of rounds.  The payoffs are the values of the subgames
+    /// the round leads to, taken from the checkpoint in `--solutions-dir`.
+    /// Without this the round is played on its own.
+    #[clap(long, requires = "solutions-dir")]
+    subgame: Option<Subgame>,
+
+    /// The output directory of a finished `tictac solve-match` run.
+    #[clap(short, long, parse(from_os_str), value_hint = ValueHint::DirPath)]
+    solutions_dir: Option<std::path::PathBuf>,
+
+    #[clap(flatten)]
+    tree: TreeArgs,
+}
+
+pub fn run(args: Args) {
+    let game_tree = args.tree.build();
+
+    let (outcome_values, title) = match (&args.subgame, &args.solutions_dir) {
+        (Some(subgame), Some(solutions_dir)) => {
+            let (outcome_values, format) = or_exit(
+                io::subgame_outcome_values(solutions_dir, subgame, &game_tree),
+                "couldn't read the match's values",
+            );
+            (
+                outcome_values,
+                format!("Subgame {} of a match, {}", subgame, format.describe()),
+            )
+        }
+        _ => (OutcomeValues::default(), "Single round".to_string()),
+    };
+    log::debug!("Outcome values are: {:?}", outcome_values);
+
+    log::info!("Writing {}...", args.output.display());
+    let mut out = BufWriter::new(File::create(&args.output).expect("couldn't create file"));
+    gambit::write_efg(&mut out, &game_tree, &outcome_values, &title)
+        .and_then(|_| out.flush())
+        .expect("couldn't write file");
+}
@@ -0,0 +1,78 @@
+use clap::ValueHint;
+use std::path::PathBuf;
+
+use tictac::cli::*;
+use tictac::play::head_to_head;
+
+use crate::or_exit;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// The first bot: an output directory of `tictac solve-match`, or
+    /// "uniform" for uniformly random play.
+    #[clap(parse(from_os_str), value_hint = ValueHint::DirPath)]
+    bot_a: PathBuf,
+
+    /// The second bot, like the first.
+    #[clap(parse(from_os_str), value_hint = ValueHint::DirPath)]
+    bot_b: PathBuf,
+
+    #[clap(flatten)]
+    game: MatchArgs,
+
+    #[clap(flatten)]
+    threads: ThreadArgs,
+
+    #[clap(flatten)]
+    tree: TreeArgs,
+}
+
+pub fn run(args: Args) {
+    args.threads.init();
+    let game_tree = args.tree.build();
+
+    let bots = [&args.bot_a, &args.bot_b].map(|path| {
+        or_exit(
+            args.game.load_bot(&game_tree, path),
+            "couldn't load solutions",
+        )
+    });
+    let win_probs = or_exit(
+        head_to_head(&game_tree, [&bots[0], &bots[1]]),
+        "couldn't compare the bots",
+    );
+
+    let format = args.game.format.match_format();
+    println!(
+        "A is {}, B is {}, {}",
+        args.bot_a.display(),
+        args.bot_b.display(),
+        format.describe()
+    );
+    println!(
+        "Probability that A wins from each subgame, written as the first player's score, \
+         second player's score:"
+    );
+    for subgame in format.subgames() {
+        let a_first = win_probs[&(subgame.clone(), 0)][0];
+        let b_first = win_probs[&(subgame.clone(), 1)][0];
+        println!(
+            "  {} {:.6} if A moves first, {:.6} if B moves first",
+            subgame, a_first, b_first
+        );
+    }
+    let first_round = format.first_round();
+    let [a_first, b_first] = [0, 1].map(|first| win_probs[&(first_round.clone(), first)]);
+    println!(
+        "A wins a whole match with probability {} moving first, {} moving second, {} on average",
+        a_first[0],
+        b_first[0],
+        (a_first[0] + b_first[0]) / 2.0
+    );
+    if format.can_draw() {
+        println!(
+            "B wins with probability {} when A moves first, {} when B does",
+            a_first[1], b_first[1]
+        );
+    }
+}
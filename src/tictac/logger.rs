@@ -0,0 +1,34 @@
+//! Where log messages go.
+
+use tictac::cli::VerbosityArgs;
+
+/// Prints each message on its own line, warnings and errors to stderr and
+/// everything else to stdout.
+struct Logger;
+
+static LOGGER: Logger = Logger;
+
+impl log::Log for Logger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        if record.level() <= log::Level::Warn {
+            eprintln!("{}", record.args());
+        } else {
+            println!("{}", record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Print log messages at `verbosity` for the rest of the program.
+pub fn init(verbosity: &VerbosityArgs) {
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(verbosity.level());
+}
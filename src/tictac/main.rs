@@ -0,0 +1,107 @@
+use clap::Parser;
+use clap::Subcommand;
+use std::fmt::Display;
+
+use tictac::cli::VerbosityArgs;
+
+mod best_response;
+mod convert;
+mod exploitability;
+mod explore;
+mod export_efg;
+mod head_to_head;
+mod logger;
+mod play;
+mod serve;
+mod simulate;
+mod solve;
+mod solve_exact;
+mod solve_match;
+mod solve_mccfr;
+mod strategy_book;
+
+/// Solve, evaluate and play SMBC's "Imperfect Information Tic Tac Toe".
+#[derive(Parser)]
+struct Cli {
+    #[clap(subcommand)]
+    command: Command,
+
+    #[clap(flatten)]
+    verbosity: VerbosityArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Solve a single round with CFR.
+    Solve(solve::Args),
+    /// Solve every round of a match, from the last scores to the first.
+    SolveMatch(solve_match::Args),
+    /// Solve a single round with Monte Carlo CFR.
+    SolveMccfr(solve_mccfr::Args),
+    /// Solve a single round exactly with the sequence form linear program.
+    SolveExact(solve_exact::Args),
+    /// Compute and save the best response to a single round's solution.
+    BestResponse(best_response::Args),
+    /// Bound how much best responses gain against a match's solution.
+    Exploitability(exploitability::Args),
+    /// Step through a single round's solution state by state.
+    Explore(explore::Args),
+    /// Play a match against the bot.
+    Play(play::Args),
+    /// Play two bots against each other for many matches.
+    Simulate(simulate::Args),
+    /// Compute the exact probability that one bot wins a match against
+    /// another.
+    HeadToHead(head_to_head::Args),
+    /// Play the bot over a JSON API on localhost, with a board to play on in
+    /// the browser at /.
+    ///
+    /// POST /api/matches starts a match, GET /api/matches/ID shows it, and
+    /// POST /api/matches/ID/moves with a body like {"square": 5} plays a
+    /// move.  Squares count from 1 in row-major order, and the bot answers
+    /// straight away.
+    Serve(serve::Args),
+    /// Write a strategy out as a readable book of every decision point it
+    /// reaches.
+    StrategyBook(strategy_book::Args),
+    /// Write a single round as a Gambit .efg file.
+    ExportEfg(export_efg::Args),
+    /// Convert a strategy, best response or solver state between bincode,
+    /// JSON and CSV.
+    ///
+    /// Formats are chosen by the .bincode, .json or .csv extension.
+    Convert(convert::Args),
+}
+
+/// The value of `result`, or if it failed print `context` and the error and
+/// exit.
+fn or_exit<T, E: Display>(result: Result<T, E>, context: &str) -> T {
+    match result {
+        Ok(value) => value,
+        Err(e) => {
+            eprintln!("{}: {}", context, e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn main() {
+    let cli = Cli::parse();
+    logger::init(&cli.verbosity);
+    match cli.command {
+        Command::Solve(args) => solve::run(args),
+        Command::SolveMatch(args) => solve_match::run(args),
+        Command::SolveMccfr(args) => solve_mccfr::run(args),
+        Command::SolveExact(args) => solve_exact::run(args),
+        Command::BestResponse(args) => best_response::run(args),
+        Command::Exploitability(args) => exploitability::run(args),
+        Command::Explore(args) => explore::run(args),
+        Command::Play(args) => play::run(args),
+        Command::Simulate(args) => simulate::run(args),
+        Command::HeadToHead(args) => head_to_head::run(args),
+        Command::Serve(args) => serve::run(args),
+        Command::StrategyBook(args) => strategy_book::run(args),
+        Command::ExportEfg(args) => export_efg::run(args),
+        Command::Convert(args) => convert::run(args),
+    }
+}
@@ -0,0 +1,167 @@
+use clap::ValueHint;
+use rand::thread_rng;
+use std::io::Write;
+
+use tictac::cli::*;
+use tictac::play::{Match, Side};
+use tictac::*;
+
+use crate::or_exit;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// The output directory of `tictac solve-match`, or of `tictac solve` to
+    /// play every round with the same solution.
+    #[clap(short, long, parse(from_os_str), value_hint = ValueHint::DirPath)]
+    solutions_dir: std::path::PathBuf,
+
+    /// The iteration to load from the output of `tictac solve`.  Defaults to
+    /// the last one saved.
+    #[clap(short, long)]
+    iteration: Option<usize>,
+
+    #[clap(flatten)]
+    game: MatchArgs,
+
+    /// Show what the bot believes your goal is, and what its moves so far say
+    /// about its own goal.
+    #[clap(long)]
+    hints: bool,
+
+    #[clap(flatten)]
+    tree: TreeArgs,
+}
+
+fn describe_round(game: &Match) {
+    let subgame = game.subgame();
+    log::info!(
+        "Loading iteration {} for subgame {}",
+        game.solution.iteration,
+        subgame
+    );
+    log::info!("{}", game.describe_strategy());
+}
+
+/// Show the posteriors over each side's goal, assuming both sides play the
+/// bot's strategy.
+fn print_beliefs(
+    game_tree: &GameTree,
+    bot_strategy: &Strategy,
+    metastate: &MetaState,
+    humanplayer: Player,
+) {
+    match bot_strategy.goal_beliefs(game_tree, metastate, humanplayer.opponent()) {
+        Some(beliefs) => println!("The bot believes you want {}", beliefs),
+        None => println!("The bot never expected your moves"),
+    }
+    match bot_strategy.goal_beliefs(game_tree, metastate, humanplayer) {
+        Some(beliefs) => println!("The bot's moves suggest it wants {}", beliefs),
+        None => println!("The bot's moves don't fit your goal"),
+    }
+}
+
+pub fn run(args: Args) {
+    let game_tree = args.tree.build();
+
+    let mut rng = thread_rng();
+    let format = args.game.format.match_format();
+    let first_round = format.first_round();
+    let mut game = if io::subgame_dir(&args.solutions_dir, &first_round).is_dir() {
+        let game = or_exit(
+            Match::new(
+                &game_tree,
+                &args.solutions_dir,
+                format,
+                args.game.average_strategy,
+                &mut rng,
+            ),
+            "couldn't load solution",
+        );
+        describe_round(&game);
+        game
+    } else {
+        let solution = SolutionArgs {
+            solutions_dir: args.solutions_dir.clone(),
+            iteration: args.iteration,
+            average_strategy: args.game.average_strategy,
+        }
+        .load(&game_tree);
+        let solution = or_exit(solution, "couldn't load solution");
+        Match::single_round(
+            &game_tree,
+            solution,
+            format,
+            args.game.average_strategy,
+            &mut rng,
+        )
+    };
+
+    println!("Playing a match, {}", format.describe());
+
+    loop {
+        println!("{:?}", game.displayed.view);
+        println!(
+            "Current score: You {} Bot {}",
+            game.human_score, game.bot_score
+        );
+        println!("Your goal is: {}", game.human_goal());
+        if args.hints {
+            print_beliefs(
+                &game_tree,
+                game.bot_strategy(),
+                &game.metastate,
+                game.human_player,
+            );
+        }
+
+        if game.to_move(&game_tree) == Side::Human {
+            print!(
+                "Enter your move ( 1 through {})> ",
+                game_tree.board.num_cells()
+            );
+            std::io::stdout().flush().unwrap();
+            let mut line = String::new();
+            std::io::stdin().read_line(&mut line).unwrap();
+            match line.trim().parse::<usize>() {
+                Ok(position) => {
+                    if !(1..=game_tree.board.num_cells()).contains(&position) {
+                        println!("Bad position {}", position);
+                        continue;
+                    }
+                    if !game.play_human(&game_tree, position - 1) {
+                        println!("Invalid move!");
+                    }
+                }
+                Err(_) => {
+                    println!("Invalid move!");
+                }
+            };
+        } else {
+            game.play_bot(&game_tree, &mut rng);
+        }
+        if let Some(result) = or_exit(
+            game.finish_round(&game_tree, &mut rng),
+            "couldn't load solution",
+        ) {
+            println!("==============================");
+            println!("Round ended.  You {}.", result.outcome);
+            println!("The bot's goal was {}", result.bot_goal);
+            match game.winner() {
+                Some(Side::Bot) => {
+                    println!("The bot wins the match!");
+                    break;
+                }
+                Some(Side::Human) => {
+                    println!("You win the match!");
+                    break;
+                }
+                None if game.is_over() => {
+                    println!("The match is drawn!");
+                    break;
+                }
+                None if game.is_multiround() => describe_round(&game),
+                None => {}
+            }
+        }
+    }
+}
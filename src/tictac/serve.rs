@@ -0,0 +1,262 @@
+use clap::ValueHint;
+use rand::rngs::ThreadRng;
+use rand::thread_rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::time::{Duration, Instant};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use tictac::cli::*;
+use tictac::play::{Match, RoundResult, Side};
+use tictac::*;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// The output directory of `tictac solve-match`.
+    #[clap(short, long, parse(from_os_str), value_hint = ValueHint::DirPath)]
+    solutions_dir: std::path::PathBuf,
+
+    #[clap(flatten)]
+    game: MatchArgs,
+
+    #[clap(flatten)]
+    tree: TreeArgs,
+
+    /// The port to listen on.  Only connections from this machine are
+    /// accepted.
+    #[clap(short, long, default_value_t = 8000)]
+    port: u16,
+
+    /// Forget a match after this many minutes without a request about it.
+    #[clap(long, default_value_t = 60)]
+    idle_minutes: u64,
+}
+
+const INDEX_HTML: &str = include_str!("serve.html");
+
+/// A match, and what happened in it that the client may not have seen.
+struct Session {
+    game: Match,
+    /// The bot's latest move on the current board, counting from 1.
+    bot_move: Option<usize>,
+    /// The latest round to end.
+    last_round: Option<RoundResult>,
+    /// When the match was last asked about.
+    last_request: Instant,
+}
+
+#[derive(Serialize)]
+struct Score {
+    you: i8,
+    bot: i8,
+}
+
+#[derive(Serialize)]
+struct MatchView<'a> {
+    id: u64,
+    rows: u8,
+    cols: u8,
+    /// The move number on each square in row-major order, or 0 if it is
+    /// empty.
+    board: &'a [u8],
+    you_play: Player,
+    your_goal: Outcome,
+    to_move: Side,
+    bot_move: Option<usize>,
+    score: Score,
+    /// The rules of the match in words, e.g. "first to 5".
+    format: String,
+    over: bool,
+    /// `None` while the match goes on, and if it was drawn.
+    winner: Option<Side>,
+    last_round: &'a Option<RoundResult>,
+}
+
+#[derive(Deserialize)]
+struct MoveRequest {
+    square: usize,
+}
+
+struct App {
+    args: Args,
+    game_tree: GameTree,
+    rng: ThreadRng,
+    sessions: HashMap<u64, Session>,
+    next_id: u64,
+}
+
+type Reply = (u16, String);
+
+fn error(status: u16, message: &str) -> Reply {
+    (status, serde_json::json!({ "error": message }).to_string())
+}
+
+impl App {
+    fn view(&self, id: u64) -> Reply {
+        let session = &self.sessions[&id];
+        let game = &session.game;
+        let view = MatchView {
+            id,
+            rows: self.game_tree.board.rows,
+            cols: self.game_tree.board.cols,
+            board: game.displayed.view.cells(),
+            you_play: game.human_player,
+            your_goal: game.human_goal(),
+            to_move: game.to_move(&self.game_tree),
+            bot_move: session.bot_move,
+            score: Score {
+                you: game.human_score,
+                bot: game.bot_score,
+            },
+            format: game.format.describe(),
+            over: game.is_over(),
+            winner: game.winner(),
+            last_round: &session.last_round,
+        };
+        (200, serde_json::to_string(&view).unwrap())
+    }
+
+    /// Score finished rounds and let the bot move until it is the human's turn
+    /// or the match is over.
+    fn advance(&mut self, id: u64) -> Result<(), Reply> {
+        let session = self.sessions.get_mut(&id).unwrap();
+        loop {
+            let finished = session
+                .game
+                .finish_round(&self.game_tree, &mut self.rng)
+                .map_err(|e| error(500, &format!("couldn't load solution: {}", e)))?;
+            if let Some(result) = finished {
+                session.last_round = Some(result);
+                session.bot_move = None;
+            }
+            if session.game.is_over() || session.game.to_move(&self.game_tree) == Side::Human {
+                return Ok(());
+            }
+            session.bot_move = Some(session.game.play_bot(&self.game_tree, &mut self.rng) + 1);
+        }
+    }
+
+    fn new_match(&mut self) -> Result<Reply, Reply> {
+        let game = Match::new(
+            &self.game_tree,
+            &self.args.solutions_dir,
+            self.args.game.format.match_format(),
+            self.args.game.average_strategy,
+            &mut self.rng,
+        )
+        .map_err(|e| error(500, &format!("couldn't load solution: {}", e)))?;
+        let id = self.next_id;
+        self.next_id += 1;
+        self.sessions.insert(
+            id,
+            Session {
+                game,
+                bot_move: None,
+                last_round: None,
+                last_request: Instant::now(),
+            },
+        );
+        self.advance(id)?;
+        Ok(self.view(id))
+    }
+
+    fn play(&mut self, id: u64, body: &str) -> Result<Reply, Reply> {
+        let request: MoveRequest = serde_json::from_str(body)
+            .map_err(|e| error(400, &format!("bad move request: {}", e)))?;
+        let session = self.sessions.get_mut(&id).unwrap();
+        if session.game.is_over() {
+            return Err(error(409, "the match is over"));
+        }
+        if request.square == 0 || !session.game.play_human(&self.game_tree, request.square - 1) {
+            return Err(error(400, "invalid move"));
+        }
+        session.bot_move = None;
+        self.advance(id)?;
+        Ok(self.view(id))
+    }
+
+    fn handle(&mut self, request: &mut Request) -> Response<Cursor<Vec<u8>>> {
+        let path = request.url().split('?').next().unwrap().to_string();
+        if request.method() == &Method::Get && path == "/" {
+            return Response::from_string(INDEX_HTML).with_header(content_type("text/html"));
+        }
+        let (status, body) = self.handle_api(request, &path).unwrap_or_else(|e| e);
+        Response::from_string(body)
+            .with_status_code(status)
+            .with_header(content_type("application/json"))
+    }
+
+    fn handle_api(&mut self, request: &mut Request, path: &str) -> Result<Reply, Reply> {
+        let mut body = String::new();
+        request
+            .as_reader()
+            .read_to_string(&mut body)
+            .map_err(|_| error(400, "couldn't read request body"))?;
+        self.forget_idle();
+        let parts: Vec<&str> = path.split('/').filter(|p| !p.is_empty()).collect();
+        match (request.method(), parts.as_slice()) {
+            (Method::Post, ["api", "matches"]) => self.new_match(),
+            (Method::Get, ["api", "matches", id]) => {
+                let id = self.find(id)?;
+                Ok(self.view(id))
+            }
+            (Method::Post, ["api", "matches", id, "moves"]) => {
+                let id = self.find(id)?;
+                self.play(id, &body)
+            }
+            _ => Err(error(404, "not found")),
+        }
+    }
+
+    /// Look up a match, and note that it is still in use.
+    fn find(&mut self, id: &str) -> Result<u64, Reply> {
+        let id = id.parse::<u64>().map_err(|_| error(404, "no such match"))?;
+        let session = self
+            .sessions
+            .get_mut(&id)
+            .ok_or_else(|| error(404, "no such match"))?;
+        session.last_request = Instant::now();
+        Ok(id)
+    }
+
+    /// Forget the matches that nobody has asked about for too long, so
+    /// abandoned matches don't pile up.
+    fn forget_idle(&mut self) {
+        let timeout = Duration::from_secs(self.args.idle_minutes * 60);
+        let before = self.sessions.len();
+        self.sessions
+            .retain(|_, session| session.last_request.elapsed() < timeout);
+        if self.sessions.len() < before {
+            log::debug!(
+                "Forgot {} idle matches, {} left",
+                before - self.sessions.len(),
+                self.sessions.len()
+            );
+        }
+    }
+}
+
+fn content_type(value: &str) -> Header {
+    Header::from_bytes("Content-Type", format!("{}; charset=utf-8", value)).unwrap()
+}
+
+pub fn run(args: Args) {
+    let game_tree = args.tree.build();
+
+    let server = Server::http(("127.0.0.1", args.port)).expect("couldn't start server");
+    log::info!("Listening on http://127.0.0.1:{}/", args.port);
+    let mut app = App {
+        args,
+        game_tree,
+        rng: thread_rng(),
+        sessions: HashMap::new(),
+        next_id: 1,
+    };
+    for mut request in server.incoming_requests() {
+        let response = app.handle(&mut request);
+        if let Err(e) = request.respond(response) {
+            log::warn!("couldn't send response: {}", e);
+        }
+    }
+}
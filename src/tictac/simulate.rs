@@ -0,0 +1,161 @@
+use average::{Estimate, MeanWithError};
+use clap::ValueHint;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use tictac::cli::*;
+use tictac::play::sample_round;
+use tictac::*;
+
+use crate::or_exit;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// The first bot: an output directory of `tictac solve-match`, or
+    /// "uniform" for uniformly random play.
+    #[clap(parse(from_os_str), value_hint = ValueHint::DirPath)]
+    bot_a: PathBuf,
+
+    /// The second bot, like the first.
+    #[clap(parse(from_os_str), value_hint = ValueHint::DirPath)]
+    bot_b: PathBuf,
+
+    /// The number of matches to play.  The bots take turns moving first in
+    /// the first round.
+    #[clap(
+        short = 'n',
+        long,
+        default_value_t = 10000,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    matches: u64,
+
+    #[clap(flatten)]
+    game: MatchArgs,
+
+    /// Seed for the random number generator.
+    #[clap(long, default_value_t = 0)]
+    seed: u64,
+
+    #[clap(flatten)]
+    tree: TreeArgs,
+}
+
+/// How the rounds of one subgame went, counting the bot that moved first.
+#[derive(Default)]
+struct SubgameStats {
+    rounds: usize,
+    points: [usize; 2],
+}
+
+/// The 95% Wilson score interval for a proportion of `successes` out of
+/// `trials`.
+fn wilson_interval(successes: u64, trials: u64) -> (f64, f64) {
+    let z = 1.96;
+    let n = trials as f64;
+    let p = successes as f64 / n;
+    let center = (p + z * z / (2.0 * n)) / (1.0 + z * z / n);
+    let half_width = z * (p * (1.0 - p) / n + z * z / (4.0 * n * n)).sqrt() / (1.0 + z * z / n);
+    (center - half_width, center + half_width)
+}
+
+pub fn run(args: Args) {
+    let game_tree = args.tree.build();
+
+    let bots = [&args.bot_a, &args.bot_b].map(|path| {
+        or_exit(
+            args.game.load_bot(&game_tree, path),
+            "couldn't load solutions",
+        )
+    });
+    let names = ["A", "B"];
+    let mut rng = StdRng::seed_from_u64(args.seed);
+
+    let format = args.game.format.match_format();
+    let mut wins = [0u64, 0];
+    let mut draws = 0;
+    let mut match_length = MeanWithError::new();
+    // Keyed by the subgame and which bot moved first.
+    let mut subgames = BTreeMap::<(Subgame, usize), SubgameStats>::new();
+    for i in 0..args.matches {
+        let mut subgame = format.first_round();
+        let mut first = (i % 2) as usize;
+        let mut rounds = 0;
+        let result = loop {
+            rounds += 1;
+            let second = 1 - first;
+            let (_, outcomes) = sample_round(
+                &game_tree,
+                bots[first].get(&subgame),
+                bots[second].get(&subgame),
+                &mut rng,
+            );
+            let stats = subgames.entry((subgame.clone(), first)).or_default();
+            stats.rounds += 1;
+            stats.points[first] += outcomes.0 as usize;
+            stats.points[second] += outcomes.1 as usize;
+
+            match format.next(&subgame, outcomes) {
+                Next::Over(value) if value > 0.0 => break Some(first),
+                Next::Over(value) if value < 0.0 => break Some(second),
+                Next::Over(_) => break None,
+                Next::Round {
+                    subgame: next,
+                    swapped,
+                } => {
+                    subgame = next;
+                    if swapped {
+                        first = second;
+                    }
+                }
+            }
+        };
+        match result {
+            Some(winner) => wins[winner] += 1,
+            None => draws += 1,
+        }
+        match_length.add(rounds as f64);
+    }
+
+    println!(
+        "A is {}, B is {}, {} matches, {}",
+        args.bot_a.display(),
+        args.bot_b.display(),
+        args.matches,
+        format.describe()
+    );
+    for (bot, name) in names.iter().enumerate() {
+        let (low, high) = wilson_interval(wins[bot], args.matches);
+        println!(
+            "{} wins {:.2}% (95% confidence interval {:.2}% to {:.2}%)",
+            name,
+            100.0 * wins[bot] as f64 / args.matches as f64,
+            100.0 * low,
+            100.0 * high
+        );
+    }
+    if format.can_draw() {
+        println!("Drawn {:.2}%", 100.0 * draws as f64 / args.matches as f64);
+    }
+    println!(
+        "Average match length {:.3} ± {:.3} rounds",
+        match_length.mean(),
+        1.96 * match_length.error()
+    );
+    println!(
+        "How often A and B scored in each subgame, written as the score of the player moving \
+         first, then the other player's:"
+    );
+    for ((subgame, first), stats) in subgames.iter() {
+        println!(
+            "  {} {} first: {} rounds, A scored {:.1}%, B scored {:.1}%",
+            subgame,
+            names[*first],
+            stats.rounds,
+            100.0 * stats.points[0] as f64 / stats.rounds as f64,
+            100.0 * stats.points[1] as f64 / stats.rounds as f64
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use strum::IntoEnumIterator;
+
+use clap::ArgAction;
+use clap::ValueHint;
+
+use tictac::cli::*;
+use tictac::*;
+
+use crate::or_exit;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// The number of iterations of CFR to run.
+    #[clap(long, default_value_t = 10)]
+    iterations: usize,
+
+    #[clap(long, action = ArgAction::Set,  default_value_t = false)]
+    only_save_last: bool,
+
+    /// The path to the output directory
+    #[clap(short, long, parse(from_os_str), value_hint = ValueHint::DirPath)]
+    output_dir: std::path::PathBuf,
+
+    #[clap(flatten)]
+    solver: SolverArgs,
+
+    #[clap(flatten)]
+    log: LogArgs,
+
+    #[clap(flatten)]
+    threads: ThreadArgs,
+
+    #[clap(flatten)]
+    tree: TreeArgs,
+}
+
+pub fn run(args: Args) {
+    args.threads.init();
+    let game_tree = args.tree.build();
+
+    let mut outcome_values = OutcomeValues {
+        both_win: 0f64,
+        p1_win: 1f64,
+        p2_win: -1f64,
+        both_lose: 0f64,
+        first_move_epsilon: args.solver.small_move_epsilon,
+    };
+    let uniform = Strategy::uniform(&game_tree);
+
+    std::fs::create_dir_all(&args.output_dir).unwrap();
+    let mut manifest = args.solver.manifest("solve", &game_tree);
+    let mut cfr = CFR::new(args.solver.variant(), args.solver.alternate_updates);
+    let mut strategy = uniform.clone();
+    let log = or_exit(args.log.open(false), "couldn't open the log");
+    let mut report = None;
+    for i in 0..args.iterations {
+        log::info!("Computing CFR iteration {}...", i);
+        let new_strategy = cfr.cfr_round(&strategy, &game_tree, &outcome_values);
+
+        let max_change = new_strategy.max_difference(&strategy);
+        log::debug!("Max prob difference from old strategy {}", max_change);
+        if let Some(log) = &log {
+            if args.log.logs(i) {
+                log.record(
+                    &game_tree,
+                    i,
+                    None,
+                    &strategy,
+                    &cfr.average_strategy,
+                    max_change,
+                    &outcome_values,
+                )
+                .expect("could not write log");
+            }
+        }
+
+        if !args.only_save_last || i == args.iterations - 1 {
+            log::debug!("Saving iteration to file...");
+            io::save_iteration(&args.output_dir, i, &cfr, &strategy).expect("could not serialize");
+            let saved_report = ExploitabilityReport::new(
+                &game_tree,
+                &cfr.average_strategy,
+                &OutcomeValues::default(),
+            );
+            io::save_json(
+                &io::exploitability_report_path(&args.output_dir, i),
+                &saved_report,
+            )
+            .expect("could not write exploitability report");
+            manifest.last_iteration = Some(i);
+            manifest.exploitability = Some(saved_report.nash_conv);
+            manifest
+                .save(&args.output_dir)
+                .expect("could not write manifest");
+            report = Some(saved_report);
+        }
+        strategy = new_strategy;
+
+        outcome_values.first_move_epsilon *= 1.0 - args.solver.small_move_epsilon_decay;
+        log::debug!(
+            "Small move regularization epsilon is {}",
+            outcome_values.first_move_epsilon
+        );
+    }
+    log::info!("Finished solving!");
+    // The last iteration is always saved, so this is its report.
+    let report = report.unwrap_or_else(|| {
+        ExploitabilityReport::new(&game_tree, &cfr.average_strategy, &OutcomeValues::default())
+    });
+    println!("Exploitability of the average strategy:\n{}", report);
+    // for (s, prob) in &cfr.average_strategy.probs {
+    //     println!("State has probs {:?}:", prob);
+    //     println!("Goals {:?}", s.goal);
+    //     println!("{:?}", game_tree.states[s.state]);
+    // }
+    let expected_values = cfr
+        .average_strategy
+        .expected_values(&game_tree, &outcome_values);
+    for p1goal in Outcome::iter() {
+        for p2goal in Outcome::iter() {
+            let ret = expected_values[&MetaState {
+                state: 0,
+                p1goal,
+                p2goal,
+            }];
+            println!(
+                "EV for first player with goals {:?} {:?} {}",
+                p1goal, p2goal, ret
+            );
+        }
+    }
+    println!(
+        "Overall expected value {}",
+        expected_values.root_value(&game_tree)
+    );
+}
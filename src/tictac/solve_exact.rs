@@ -0,0 +1,57 @@
+use clap::ValueHint;
+use num_traits::ToPrimitive;
+
+use tictac::cli::*;
+use tictac::*;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// The path to the output directory.  The equilibrium is saved as
+    /// iteration 0, so the other tools can load it.
+    #[clap(short, long, parse(from_os_str), value_hint = ValueHint::DirPath)]
+    output_dir: std::path::PathBuf,
+
+    #[clap(flatten)]
+    threads: ThreadArgs,
+
+    #[clap(flatten)]
+    tree: TreeArgs,
+}
+
+pub fn run(args: Args) {
+    args.threads.init();
+    let game_tree = args.tree.build();
+
+    let outcome_values = OutcomeValues::default();
+    let solution = solve_exact(&game_tree, &outcome_values).expect("couldn't solve round");
+    log::info!("Finished solving!");
+    println!(
+        "Exact value for first player {} ({})",
+        solution.value,
+        solution.value.to_f64().unwrap()
+    );
+    let report = ExploitabilityReport::new(&game_tree, &solution.strategy, &outcome_values);
+    println!("Exploitability of the rounded strategy:\n{}", report);
+
+    log::info!("Saving equilibrium to file...");
+    std::fs::create_dir_all(&args.output_dir).unwrap();
+    io::save(&io::strategy_path(&args.output_dir, 0), &solution.strategy)
+        .expect("could not serialize");
+    io::save(
+        &io::average_strategy_path(&args.output_dir, 0),
+        &solution.strategy,
+    )
+    .expect("could not serialize");
+    io::save_json(
+        &io::exploitability_report_path(&args.output_dir, 0),
+        &report,
+    )
+    .expect("could not write exploitability report");
+    io::Manifest {
+        last_iteration: Some(0),
+        exploitability: Some(report.nash_conv),
+        ..io::Manifest::new("solve-exact", &game_tree)
+    }
+    .save(&args.output_dir)
+    .expect("could not write manifest");
+}
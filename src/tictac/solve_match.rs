@@ -0,0 +1,526 @@
+use strum::IntoEnumIterator;
+
+use clap::ValueHint;
+use rayon::prelude::*;
+use std::collections::HashMap;
+
+use super::exploitability::print_bounds;
+use tictac::cli::*;
+use tictac::convergence::ConvergenceLog;
+use tictac::*;
+
+use crate::or_exit;
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[clap(flatten)]
+    format: FormatArgs,
+
+    /// The maximum total exploitability to solve for
+    #[clap(long, default_value_t = 0.000001)]
+    maximum_subgame_exploitability: f64,
+    /// Check whether the subgames have converged every this many
+    /// iterations.  0 never checks, so the solve runs until it is stopped.
+    #[clap(long, default_value_t = 10)]
+    check_exploitability_every: i32,
+
+    /// How much the value of a subgame may differ from the value its
+    /// strategy gets against the values of the subgames it leads to.  A group
+    /// only converges once its values change by less than this between
+    /// checks, and the whole match is solved again from the first group that
+    /// is further off than this.
+    #[clap(long, default_value_t = 0.000001)]
+    value_tolerance: f64,
+    /// The most times the whole match is solved before giving up on the
+    /// values agreeing.
+    #[clap(long, default_value_t = 10)]
+    max_sweeps: usize,
+
+    /// The path to the output directory
+    #[clap(short, long, parse(from_os_str), value_hint = ValueHint::DirPath)]
+    output_dir: std::path::PathBuf,
+
+    #[clap(flatten)]
+    solver: SolverArgs,
+
+    #[clap(flatten)]
+    log: LogArgs,
+
+    /// Save a checkpoint of the whole solve every this many iterations, and
+    /// whenever a subgame converges.  0 disables checkpoints.
+    #[clap(long, default_value_t = 100)]
+    checkpoint_every: i32,
+
+    /// Continue from the checkpoint in the output directory, if there is one.
+    /// The other arguments should match the ones the checkpoint was made with.
+    #[clap(long)]
+    resume: bool,
+
+    #[clap(flatten)]
+    threads: ThreadArgs,
+
+    #[clap(flatten)]
+    tree: TreeArgs,
+}
+
+impl Args {
+    /// Whether convergence is checked after iteration `i`.
+    fn checks_exploitability(&self, i: i32) -> bool {
+        self.check_exploitability_every > 0
+            && i % self.check_exploitability_every == self.check_exploitability_every - 1
+    }
+}
+
+fn save_checkpoint(args: &Args, checkpoint: &io::MultiroundCheckpoint) {
+    if args.checkpoint_every <= 0 {
+        return;
+    }
+    log::debug!("Saving checkpoint...");
+    io::save_atomic(&io::checkpoint_path(&args.output_dir), checkpoint)
+        .expect("could not save checkpoint");
+}
+
+/// The manifest of the whole solve.  Each subgame's directory gets a copy
+/// with its own last iteration and exploitability.
+fn manifest(args: &Args, game_tree: &GameTree) -> io::Manifest {
+    io::Manifest {
+        match_format: Some(args.format.match_format().to_string()),
+        ..args.solver.manifest("solve-match", game_tree)
+    }
+}
+
+/// A group of subgames which lead to each other, from
+/// [`MatchFormat::solve_order`], which are solved together.
+struct GroupSolve {
+    subgames: Vec<Subgame>,
+    solutions: HashMap<Subgame, CFR>,
+    strategies: HashMap<Subgame, Strategy>,
+    evs: HashMap<Subgame, f64>,
+}
+
+impl GroupSolve {
+    /// Move the state of the group of `subgames` out of `checkpoint`.
+    fn take(checkpoint: &mut io::MultiroundCheckpoint, subgames: Vec<Subgame>) -> Self {
+        let mut group = GroupSolve {
+            subgames,
+            solutions: HashMap::new(),
+            strategies: HashMap::new(),
+            evs: HashMap::new(),
+        };
+        for subgame in &group.subgames {
+            if let Some(solution) = checkpoint.solutions.remove(subgame) {
+                group.solutions.insert(subgame.clone(), solution);
+            }
+            if let Some(strategy) = checkpoint.strategies.remove(subgame) {
+                group.strategies.insert(subgame.clone(), strategy);
+            }
+            if let Some(ev) = checkpoint.evs.remove(subgame) {
+                group.evs.insert(subgame.clone(), ev);
+            }
+        }
+        group
+    }
+
+    /// Move the state of this group back into `checkpoint`.
+    fn put_back(self, checkpoint: &mut io::MultiroundCheckpoint) {
+        checkpoint.solutions.extend(self.solutions);
+        checkpoint.strategies.extend(self.strategies);
+        checkpoint.evs.extend(self.evs);
+    }
+
+    /// Run `iterations` on every subgame, stopping early if they converge.
+    /// `finished_evs` holds the values of the groups solved before this one.
+    /// Returns whether the group converged.
+    fn solve(
+        &mut self,
+        args: &Args,
+        game_tree: &GameTree,
+        variant: &CFRVariant,
+        finished_evs: &HashMap<Subgame, f64>,
+        iterations: std::ops::Range<i32>,
+        log: Option<&ConvergenceLog>,
+    ) -> bool {
+        let format = args.format.match_format();
+        let mut reports = HashMap::new();
+        for i in iterations {
+            let checks = args.checks_exploitability(i);
+            let mut converged = checks;
+            for subgame in self.subgames.clone() {
+                // Subgames of this group are worth 0 until their value is
+                // first measured.  Any other subgame it leads to has to be
+                // solved already.
+                let ev = |next: &Subgame| {
+                    if self.subgames.contains(next) {
+                        return self.evs.get(next).copied().unwrap_or(0.0);
+                    }
+                    *finished_evs.get(next).unwrap_or_else(|| {
+                        panic!(
+                            "subgame {} leads to {}, which hasn't been solved",
+                            subgame, next
+                        )
+                    })
+                };
+                let outcome_values = OutcomeValues {
+                    first_move_epsilon: args.solver.small_move_epsilon
+                        * (1.0 - args.solver.small_move_epsilon_decay).powf(i as f64),
+                    ..format.outcome_values(&subgame, ev)
+                };
+                log::trace!(
+                    "Outcome values for subgame {} are: {:?}",
+                    subgame,
+                    outcome_values
+                );
+
+                log::debug!("Computing CFR iteration {} for subgame {}...", i, subgame);
+                let solution = self
+                    .solutions
+                    .entry(subgame.clone())
+                    .or_insert_with(|| CFR::new(variant.clone(), args.solver.alternate_updates));
+                let strategy = self
+                    .strategies
+                    .entry(subgame.clone())
+                    .or_insert_with(|| Strategy::uniform(game_tree));
+                let new_strategy = solution.cfr_round(strategy, game_tree, &outcome_values);
+
+                let max_change = new_strategy.max_difference(strategy);
+                log::debug!(
+                    "Max prob difference from old strategy in subgame {} {}",
+                    subgame,
+                    max_change
+                );
+                if let Some(log) = log {
+                    if args.log.logs(i as usize) {
+                        log.record(
+                            game_tree,
+                            i as usize,
+                            Some(subgame.clone()),
+                            strategy,
+                            &solution.average_strategy,
+                            max_change,
+                            &outcome_values,
+                        )
+                        .expect("could not write log");
+                    }
+                }
+                *strategy = new_strategy;
+
+                if checks {
+                    let report = ExploitabilityReport::new(
+                        game_tree,
+                        &solution.average_strategy,
+                        &outcome_values,
+                    );
+                    log::info!(
+                        "Exploitability of subgame {} is {}",
+                        subgame,
+                        report.nash_conv
+                    );
+
+                    if report.nash_conv > args.maximum_subgame_exploitability {
+                        converged = false;
+                    }
+                    reports.insert(subgame.clone(), report);
+
+                    // Compute expected values based on average strategy instead of latest.
+                    let expected_values = solution
+                        .average_strategy
+                        .expected_values(game_tree, &outcome_values);
+                    let ev = expected_values.root_value(game_tree);
+                    let ev_change = match self.evs.get(&subgame) {
+                        Some(old_ev) => f64::abs(ev - old_ev),
+                        None => f64::INFINITY,
+                    };
+                    log::debug!("Value of subgame {} changed by {}", subgame, ev_change);
+                    if ev_change > args.value_tolerance {
+                        converged = false;
+                    }
+                    self.evs.insert(subgame, ev);
+                }
+            }
+            if converged {
+                for subgame in &self.subgames {
+                    let report = &reports[subgame];
+                    log::info!("Subgame {} converged, saving it to file...", subgame);
+                    println!(
+                        "Exploitability of the average strategy in subgame {}:\n{}",
+                        subgame, report
+                    );
+                    let subgame_dir = io::subgame_dir(&args.output_dir, subgame);
+                    std::fs::create_dir_all(&subgame_dir).unwrap();
+                    io::save_json(
+                        &io::exploitability_report_path(&subgame_dir, i as usize),
+                        report,
+                    )
+                    .expect("could not write exploitability report");
+                    io::save_iteration(
+                        &subgame_dir,
+                        i as usize,
+                        &self.solutions[subgame],
+                        &self.strategies[subgame],
+                    )
+                    .expect("could not serialize");
+                    io::Manifest {
+                        subgame: Some(subgame.clone()),
+                        last_iteration: Some(i as usize),
+                        exploitability: Some(report.nash_conv),
+                        ..manifest(args, game_tree)
+                    }
+                    .save(&subgame_dir)
+                    .expect("could not write manifest");
+                }
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// Solve the groups from `checkpoint.level` up to the first round.
+fn solve_groups(
+    args: &Args,
+    game_tree: &GameTree,
+    variant: &CFRVariant,
+    checkpoint: &mut io::MultiroundCheckpoint,
+    log: Option<&ConvergenceLog>,
+) {
+    let solve_order = checkpoint.format.solve_order();
+    // Groups on the same level don't depend on each other, so they are
+    // solved in parallel.  They are brought back together every
+    // `checkpoint_every` iterations to save a checkpoint.
+    while checkpoint.level < solve_order.len() {
+        let level = &solve_order[checkpoint.level];
+        let unconverged: Vec<Vec<Subgame>> = level
+            .iter()
+            .filter(|group| !checkpoint.converged.contains(&group[0]))
+            .cloned()
+            .collect();
+        let mut groups: Vec<GroupSolve> = unconverged
+            .into_iter()
+            .map(|subgames| GroupSolve::take(checkpoint, subgames))
+            .collect();
+
+        let first_iteration = checkpoint.iteration;
+        let last_iteration = if args.checkpoint_every > 0 {
+            (first_iteration / args.checkpoint_every + 1) * args.checkpoint_every
+        } else {
+            i32::MAX
+        };
+        let finished_evs = &checkpoint.evs;
+        let converged: Vec<bool> = groups
+            .par_iter_mut()
+            .map(|group| {
+                group.solve(
+                    args,
+                    game_tree,
+                    variant,
+                    finished_evs,
+                    first_iteration..last_iteration,
+                    log,
+                )
+            })
+            .collect();
+
+        for (group, converged) in groups.into_iter().zip(converged) {
+            if converged {
+                checkpoint.converged.push(group.subgames[0].clone());
+            }
+            group.put_back(checkpoint);
+        }
+        checkpoint.iteration = last_iteration;
+        if level
+            .iter()
+            .all(|group| checkpoint.converged.contains(&group[0]))
+        {
+            checkpoint.level += 1;
+            if let Some(level) = solve_order.get(checkpoint.level) {
+                checkpoint.iteration = start_iteration(args, level);
+            }
+            checkpoint.converged.clear();
+        }
+        save_checkpoint(args, checkpoint);
+    }
+}
+
+/// How far the value of each subgame is from what its average strategy gets
+/// against the values of the subgames it leads to.
+fn inconsistencies(
+    game_tree: &GameTree,
+    checkpoint: &io::MultiroundCheckpoint,
+) -> HashMap<Subgame, f64> {
+    let subgames: Vec<&Subgame> = checkpoint.solutions.keys().collect();
+    subgames
+        .into_par_iter()
+        .map(|subgame| {
+            let outcome_values = checkpoint
+                .format
+                .outcome_values(subgame, |s| checkpoint.evs[s]);
+            let ev = checkpoint.solutions[subgame]
+                .average_strategy
+                .expected_values(game_tree, &outcome_values)
+                .root_value(game_tree);
+            (subgame.clone(), f64::abs(ev - checkpoint.evs[subgame]))
+        })
+        .collect()
+}
+
+/// Set `checkpoint` up to solve again from the first level with a group that
+/// has a subgame further off than the tolerance.  Everything after it in the
+/// solve order depends on its values, so it is solved again too, carrying on
+/// from where it was.
+fn reopen(
+    args: &Args,
+    checkpoint: &mut io::MultiroundCheckpoint,
+    inconsistencies: &HashMap<Subgame, f64>,
+) {
+    let off = |group: &Vec<Subgame>| {
+        group
+            .iter()
+            .any(|subgame| inconsistencies[subgame] > args.value_tolerance)
+    };
+    let solve_order = checkpoint.format.solve_order();
+    let level = solve_order
+        .iter()
+        .position(|level| level.iter().any(off))
+        .unwrap();
+    log::info!("Solving again from level {} of the solve order", level);
+    checkpoint.converged = solve_order[level]
+        .iter()
+        .filter(|group| !off(group))
+        .map(|group| group[0].clone())
+        .collect();
+    checkpoint.level = level;
+    checkpoint.iteration = start_iteration(args, &solve_order[level]);
+}
+
+/// The iteration to start solving `level` at: 0 the first time, and
+/// otherwise one past the last iteration saved for any of its subgames, so
+/// that solving it again doesn't overwrite the files earlier manifests name.
+fn start_iteration(args: &Args, level: &[Vec<Subgame>]) -> i32 {
+    level
+        .iter()
+        .flatten()
+        .filter_map(|subgame| {
+            let dir = io::subgame_dir(&args.output_dir, subgame);
+            if !dir.exists() {
+                return None;
+            }
+            io::latest_iteration(&dir).expect("couldn't read subgame directory")
+        })
+        .max()
+        .map_or(0, |iteration| iteration as i32 + 1)
+}
+
+pub fn run(args: Args) {
+    args.threads.init();
+    std::fs::create_dir_all(&args.output_dir).unwrap();
+    let game_tree = args.tree.build();
+    let variant = args.solver.variant();
+
+    let format = args.format.match_format();
+    let mut checkpoint = io::MultiroundCheckpoint::new(format, &game_tree);
+    if args.resume {
+        let path = io::checkpoint_path(&args.output_dir);
+        match io::load_if_exists::<io::MultiroundCheckpoint>(&path)
+            .expect("couldn't load checkpoint")
+        {
+            Some(loaded) => {
+                if loaded.format != format {
+                    eprintln!(
+                        "Checkpoint {} was made with --format {}",
+                        path.display(),
+                        loaded.format
+                    );
+                    std::process::exit(1);
+                }
+                if let Err(e) = loaded.check_tree(&game_tree) {
+                    eprintln!("Checkpoint {} {}", path.display(), e);
+                    std::process::exit(1);
+                }
+                log::info!(
+                    "Resuming level {} of the solve order at iteration {} from {}",
+                    loaded.level,
+                    loaded.iteration,
+                    path.display()
+                );
+                checkpoint = loaded;
+            }
+            None => log::info!("No checkpoint at {}, starting from scratch", path.display()),
+        }
+    }
+    // Only once the checkpoint is known to fit, so a refused resume leaves
+    // the manifest describing the solve that is there.
+    manifest(&args, &game_tree)
+        .save(&args.output_dir)
+        .expect("could not write manifest");
+
+    // A resumed solve carries on with the same log.
+    let log = or_exit(args.log.open(args.resume), "couldn't open the log");
+    let mut sweep = 1;
+    loop {
+        solve_groups(&args, &game_tree, &variant, &mut checkpoint, log.as_ref());
+        let inconsistencies = inconsistencies(&game_tree, &checkpoint);
+        let worst = inconsistencies.values().cloned().fold(0.0, f64::max);
+        log::info!(
+            "After sweep {} the subgame values agree to within {}",
+            sweep,
+            worst
+        );
+        if worst <= args.value_tolerance {
+            break;
+        }
+        if sweep >= args.max_sweeps {
+            log::warn!(
+                "Giving up after {} sweeps, the values still differ by more than {}",
+                sweep,
+                args.value_tolerance
+            );
+            break;
+        }
+        reopen(&args, &mut checkpoint, &inconsistencies);
+        save_checkpoint(&args, &checkpoint);
+        sweep += 1;
+    }
+
+    log::info!("Finished solving!");
+
+    println!("EV's for the subgames:");
+    for subgame in format.solve_order().into_iter().flatten().flatten() {
+        println!("{} Value {}", subgame, checkpoint.evs[&subgame]);
+    }
+
+    println!("EVs for the overall game:");
+    let first_round_cfr = &checkpoint.solutions[&format.first_round()];
+    let expected_values = &first_round_cfr.expected_value;
+    for p1goal in Outcome::iter() {
+        for p2goal in Outcome::iter() {
+            let ret = expected_values[&MetaState {
+                state: 0,
+                p1goal,
+                p2goal,
+            }];
+            println!(
+                "EV for first player with goals {:?} {:?} {}",
+                p1goal, p2goal, ret
+            );
+        }
+    }
+    println!(
+        "Overall expected value {}",
+        expected_values.root_value(&game_tree)
+    );
+
+    log::info!("Computing the exploitability of the match...");
+    let strategies = checkpoint
+        .solutions
+        .iter()
+        .map(|(subgame, solution)| (subgame.clone(), &solution.average_strategy))
+        .collect();
+    let bounds = match_exploitability(&game_tree, &format, &strategies);
+    print_bounds(&bounds, &format);
+    io::Manifest {
+        exploitability: Some(bounds[&format.first_round()].exploitability()),
+        ..manifest(&args, &game_tree)
+    }
+    .save(&args.output_dir)
+    .expect("could not write manifest");
+}
@@ -0,0 +1,127 @@
+use strum::IntoEnumIterator;
+
+use clap::ArgAction;
+use clap::ValueEnum;
+use clap::ValueHint;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use tictac::cli::*;
+use tictac::*;
+
+#[derive(ValueEnum, Clone)]
+enum SamplingArg {
+    /// External sampling.
+    External,
+    /// Outcome sampling.
+    Outcome,
+}
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// The number of iterations to run.  Each iteration is `--traversals`
+    /// sampled traversals per player, and is saved to its own files.
+    #[clap(long, default_value_t = 10)]
+    iterations: usize,
+
+    /// The number of sampled traversals per player in each iteration.
+    #[clap(long, default_value_t = 10000)]
+    traversals: usize,
+
+    #[clap(long, value_enum, default_value_t = SamplingArg::External)]
+    sampling: SamplingArg,
+
+    /// With outcome sampling, how much the updated player's moves are
+    /// sampled from the uniform strategy rather than their own.
+    #[clap(long, default_value_t = 0.6)]
+    exploration: f64,
+
+    /// Seed for the random number generator.
+    #[clap(long, default_value_t = 0)]
+    seed: u64,
+
+    #[clap(long, action = ArgAction::Set,  default_value_t = false)]
+    only_save_last: bool,
+
+    /// The path to the output directory
+    #[clap(short, long, parse(from_os_str), value_hint = ValueHint::DirPath)]
+    output_dir: std::path::PathBuf,
+
+    #[clap(flatten)]
+    threads: ThreadArgs,
+
+    #[clap(flatten)]
+    tree: TreeArgs,
+}
+
+pub fn run(args: Args) {
+    args.threads.init();
+    let game_tree = args.tree.build();
+
+    let outcome_values = OutcomeValues::default();
+    let sampling = match args.sampling {
+        SamplingArg::External => Sampling::External,
+        SamplingArg::Outcome => Sampling::Outcome {
+            exploration: args.exploration,
+        },
+    };
+    let mut rng = StdRng::seed_from_u64(args.seed);
+    std::fs::create_dir_all(&args.output_dir).unwrap();
+    let mut manifest = io::Manifest::new("solve-mccfr", &game_tree);
+    let mut mccfr = MCCFR::new(&game_tree, sampling);
+    for i in 0..args.iterations {
+        log::info!("Computing MCCFR iteration {}...", i);
+        mccfr.run(&game_tree, &outcome_values, args.traversals, &mut rng);
+        let average_strategy = mccfr.average_strategy();
+        let report = ExploitabilityReport::new(&game_tree, &average_strategy, &outcome_values);
+        log::info!("Exploitability is {}", report.nash_conv);
+
+        if !args.only_save_last || i == args.iterations - 1 {
+            log::debug!("Saving iteration to file...");
+            io::save(
+                &io::strategy_path(&args.output_dir, i),
+                &mccfr.current_strategy(&game_tree),
+            )
+            .expect("could not serialize");
+            io::save(
+                &io::average_strategy_path(&args.output_dir, i),
+                &average_strategy,
+            )
+            .expect("could not serialize");
+            io::save_json(
+                &io::exploitability_report_path(&args.output_dir, i),
+                &report,
+            )
+            .expect("could not write exploitability report");
+            manifest.last_iteration = Some(i);
+            manifest.exploitability = Some(report.nash_conv);
+            manifest
+                .save(&args.output_dir)
+                .expect("could not write manifest");
+        }
+        if i == args.iterations - 1 {
+            println!("Exploitability of the average strategy:\n{}", report);
+        }
+    }
+    log::info!("Finished solving!");
+    let expected_values = mccfr
+        .average_strategy()
+        .expected_values(&game_tree, &outcome_values);
+    for p1goal in Outcome::iter() {
+        for p2goal in Outcome::iter() {
+            let ret = expected_values[&MetaState {
+                state: 0,
+                p1goal,
+                p2goal,
+            }];
+            println!(
+                "EV for first player with goals {:?} {:?} {}",
+                p1goal, p2goal, ret
+            );
+        }
+    }
+    println!(
+        "Overall expected value {}",
+        expected_values.root_value(&game_tree)
+    );
+}
@@ -0,0 +1,288 @@
+use clap::ValueEnum;
+use clap::ValueHint;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use strum::IntoEnumIterator;
+
+use tictac::cli::*;
+use tictac::*;
+
+use crate::or_exit;
+
+#[derive(ValueEnum, Clone, Copy, PartialEq)]
+enum Format {
+    Markdown,
+    Text,
+}
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[clap(flatten)]
+    solution: SolutionArgs,
+
+    /// Write the book for the round of a match at these scores, written as
+    /// P1SCORE,P2SCORE or P1SCORE,P2SCORE,ROUND, from the output of
+    /// `tictac solve-match` in `--solutions-dir`.  The values of moves then
+    /// count the subgames the round leads to, taken from its checkpoint.
+    #[clap(long)]
+    subgame: Option<Subgame>,
+
+    /// The path of the book to write.
+    #[clap(short, long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    output: std::path::PathBuf,
+
+    #[clap(long, value_enum, default_value_t = Format::Markdown)]
+    format: Format,
+
+    /// Leave out decision points that a player with that goal reaches less
+    /// often than this, and moves played less often than this.
+    #[clap(long, default_value_t = 0.01)]
+    min_prob: f64,
+
+    #[clap(flatten)]
+    tree: TreeArgs,
+}
+
+/// A decision point of the book: an information state, how likely a player
+/// with its goal is to reach it, and its value and each move's value to the
+/// player to move.
+struct Entry {
+    infostate: InfoState,
+    reach_prob: f64,
+    value: f64,
+    move_values: Vec<f64>,
+}
+
+/// The board with the move number on each square, without colours.
+fn board_rows(state: &State) -> Vec<String> {
+    let width = if state.board.num_cells() >= 10 { 2 } else { 1 };
+    state
+        .cells()
+        .chunks(state.board.cols as usize)
+        .map(|row| {
+            row.iter()
+                .map(|&m| match m {
+                    0 => format!("{:>width$}", "."),
+                    m => format!("{:>width$}", m),
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect()
+}
+
+fn entries(
+    tree: &GameTree,
+    strategy: &Strategy,
+    outcome_values: &OutcomeValues,
+    min_prob: f64,
+) -> Vec<Entry> {
+    let visit_probs = strategy.visit_probs(tree);
+    let values = strategy.expected_values(tree, outcome_values);
+    let mut result = Vec::new();
+    for state in 0..tree.states.len() {
+        if tree.terminals[state].is_some() {
+            continue;
+        }
+        let player = tree.current_player[state];
+        let sign = match player {
+            Player::Player1 => 1.0,
+            Player::Player2 => -1.0,
+        };
+        for goal in Outcome::iter() {
+            let metastates: Vec<MetaState> = Outcome::iter()
+                .map(|other_goal| {
+                    let (p1goal, p2goal) = match player {
+                        Player::Player1 => (goal, other_goal),
+                        Player::Player2 => (other_goal, goal),
+                    };
+                    MetaState {
+                        state,
+                        p1goal,
+                        p2goal,
+                    }
+                })
+                .collect();
+            let goal_prob: f64 = metastates
+                .iter()
+                .map(|m| tree.goals.prob(m.p1goal, m.p2goal))
+                .sum();
+            let reach: f64 = metastates.iter().map(|m| visit_probs[m]).sum();
+            if goal_prob == 0.0 || reach / goal_prob < min_prob {
+                continue;
+            }
+            let average = |value: &dyn Fn(&MetaState) -> f64| {
+                let total: f64 = metastates.iter().map(|m| visit_probs[m] * value(m)).sum();
+                sign * total / reach
+            };
+            result.push(Entry {
+                infostate: InfoState { state, goal },
+                reach_prob: reach / goal_prob,
+                value: average(&|m| values[m]),
+                move_values: (0..tree.children[state].len())
+                    .map(|i| average(&|m| values[&m.children(tree)[i]]))
+                    .collect(),
+            });
+        }
+    }
+    // Earlier moves first.
+    result.sort_by_key(|entry| {
+        let moves = tree.states[entry.infostate.state]
+            .cells()
+            .iter()
+            .max()
+            .copied();
+        (moves, entry.infostate.state, entry.infostate.goal.index())
+    });
+    result
+}
+
+fn write_book<W: Write>(
+    out: &mut W,
+    args: &Args,
+    tree: &GameTree,
+    strategy: &Strategy,
+    entries: &[Entry],
+    description: &str,
+) -> std::io::Result<()> {
+    let markdown = args.format == Format::Markdown;
+    if markdown {
+        writeln!(out, "# Strategy book\n")?;
+    }
+    writeln!(out, "{}", description)?;
+    writeln!(
+        out,
+        "Board {}, goals dealt {}.  Decision points reached less than {}% of the time by a \
+         player with that goal, and moves played less than {}% of the time, are left out.  \
+         Values are for the player to move: in a single round 1 means only they score and -1 \
+         only the other player does, and in a round of a match 1 is winning the match and -1 \
+         losing it.  Squares are numbered from 1 in row-major order.",
+        tree.board,
+        tree.goals,
+        100.0 * args.min_prob,
+        100.0 * args.min_prob
+    )?;
+
+    let mut last_move_num = None;
+    for entry in entries.iter() {
+        let state = &tree.states[entry.infostate.state];
+        let move_num = state.cells().iter().max().copied().unwrap_or(0) + 1;
+        if last_move_num != Some(move_num) {
+            last_move_num = Some(move_num);
+            writeln!(out)?;
+            if markdown {
+                writeln!(out, "## Move {}", move_num)?;
+            } else {
+                writeln!(out, "==== Move {} ====", move_num)?;
+            }
+        }
+        let player = match tree.current_player[entry.infostate.state] {
+            Player::Player1 => "Player 1",
+            Player::Player2 => "Player 2",
+        };
+        writeln!(out)?;
+        let heading = format!(
+            "{} to move with goal {}, reached {:.1}% of the time, value {:.3}",
+            player,
+            entry.infostate.goal,
+            100.0 * entry.reach_prob,
+            entry.value
+        );
+        if markdown {
+            writeln!(out, "### {}\n\n```", heading)?;
+        } else {
+            writeln!(out, "{}", heading)?;
+        }
+        for row in board_rows(state) {
+            writeln!(out, "{}", row)?;
+        }
+        if markdown {
+            writeln!(
+                out,
+                "```\n\n| Square | Probability | Value |\n|---|---|---|"
+            )?;
+        }
+        let probs = &strategy.probs[&entry.infostate];
+        let mut left_out = 0;
+        for (i, &child) in tree.children[entry.infostate.state].iter().enumerate() {
+            if probs[i] < args.min_prob {
+                left_out += 1;
+                continue;
+            }
+            let square = tree.states[child].last_move().unwrap() + 1;
+            if markdown {
+                writeln!(
+                    out,
+                    "| {} | {:.1}% | {:.3} |",
+                    square,
+                    100.0 * probs[i],
+                    entry.move_values[i]
+                )?;
+            } else {
+                writeln!(
+                    out,
+                    "  square {}: {:.1}%, value {:.3}",
+                    square,
+                    100.0 * probs[i],
+                    entry.move_values[i]
+                )?;
+            }
+        }
+        if left_out > 0 {
+            if markdown {
+                writeln!(out)?;
+            }
+            writeln!(
+                out,
+                "{}{} rarely played move{} left out",
+                if markdown { "" } else { "  " },
+                left_out,
+                if left_out == 1 { "" } else { "s" }
+            )?;
+        }
+    }
+    Ok(())
+}
+
+pub fn run(args: Args) {
+    let game_tree = args.tree.build();
+
+    let (dir, outcome_values) = match &args.subgame {
+        Some(subgame) => {
+            let solutions_dir = &args.solution.solutions_dir;
+            let (outcome_values, _) = or_exit(
+                io::subgame_outcome_values(solutions_dir, subgame, &game_tree),
+                "couldn't read the match's values",
+            );
+            (io::subgame_dir(solutions_dir, subgame), outcome_values)
+        }
+        None => (
+            args.solution.solutions_dir.clone(),
+            OutcomeValues::default(),
+        ),
+    };
+    let solution = or_exit(
+        args.solution.load_from(&dir, &game_tree),
+        "couldn't load solution",
+    );
+    let description = solution.describe(args.solution.average_strategy);
+    let (strategy, _) = solution.bot_strategy(args.solution.average_strategy);
+
+    let entries = entries(&game_tree, strategy, &outcome_values, args.min_prob);
+    log::info!(
+        "Writing {} decision points to {}...",
+        entries.len(),
+        args.output.display()
+    );
+    let mut out = BufWriter::new(File::create(&args.output).expect("couldn't create file"));
+    write_book(
+        &mut out,
+        &args,
+        &game_tree,
+        strategy,
+        &entries,
+        &(description + "."),
+    )
+    .and_then(|_| out.flush())
+    .expect("couldn't write file");
+}